@@ -1,5 +1,6 @@
 use std::fs::{remove_file, File};
 use std::path::PathBuf;
+use std::str::FromStr;
 use std::time::Duration;
 
 use libflate::gzip;
@@ -8,20 +9,59 @@ use tools::{teprintln, tprintln, Address};
 
 use deduplicator::{
     deduplicator::{DedupeConfig, Deduplicator},
-    sources::Source,
+    sources::{RankingConfig, Source},
     utils::{load_from_sqlite, parse_duration},
 };
 
+/// A source read with a generic importer, given as `name=config:path` where `config` is the
+/// mapping file of the importer.
+#[derive(Debug)]
+struct CustomSource {
+    name: String,
+    config: PathBuf,
+    path: PathBuf,
+}
+
+impl FromStr for CustomSource {
+    type Err = String;
+
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let (name, config_path) = raw
+            .split_once('=')
+            .ok_or_else(|| format!("expected `name=config:path`, got `{}`", raw))?;
+
+        let (config, path) = config_path
+            .split_once(':')
+            .ok_or_else(|| format!("expected `name=config:path`, got `{}`", raw))?;
+
+        Ok(Self {
+            name: name.to_string(),
+            config: config.into(),
+            path: path.into(),
+        })
+    }
+}
+
 #[derive(Debug, StructOpt)]
 #[structopt(
     name = "deduplicator",
     about = "Deduplicate addresses from several sources."
 )]
 struct Params {
+    /// Path to data from the BAN (Base Adresse Nationale), as CSV files possibly compressed with
+    /// gzip
+    #[structopt(long)]
+    ban: Vec<PathBuf>,
+
     /// Path to data from bano
     #[structopt(long)]
     bano: Vec<PathBuf>,
 
+    /// Path to data from the NAD (National Address Database of the United States), as its text
+    /// export possibly in a zip archive
+    #[structopt(long)]
+    nad: Vec<PathBuf>,
+
     /// Path to data from OpenAddress
     #[structopt(long)]
     openaddresses: Vec<PathBuf>,
@@ -30,10 +70,36 @@ struct Params {
     #[structopt(long)]
     osm: Vec<PathBuf>,
 
+    /// Data read with the generic CSV importer, as `name=config:path` where `config` is the
+    /// mapping file describing the columns of the CSV file at `path`, and the priority of the
+    /// source.
+    #[structopt(long)]
+    csv: Vec<CustomSource>,
+
+    /// Data read with the generic GeoJSON importer, as `name=config:path` where `config` is the
+    /// mapping file describing the properties of the features of the GeoJSON file at `path`, and
+    /// the priority of the source.
+    #[structopt(long)]
+    geojson: Vec<CustomSource>,
+
+    /// Data read with the generic GeoPackage importer, as `name=config:path` where `config` is
+    /// the mapping file describing the columns of the feature tables of the GeoPackage file at
+    /// `path`, and the priority of the source.
+    #[structopt(long)]
+    gpkg: Vec<CustomSource>,
+
+    /// Path to data from the BAN as an SQLite database
+    #[structopt(long)]
+    ban_db: Vec<PathBuf>,
+
     /// Path to data from Bano as an SQLite database
     #[structopt(long)]
     bano_db: Vec<PathBuf>,
 
+    /// Path to data from the NAD as an SQLite database
+    #[structopt(long)]
+    nad_db: Vec<PathBuf>,
+
     /// Path to data from OpenAddress as an SQLite database
     #[structopt(long)]
     openaddresses_db: Vec<PathBuf>,
@@ -42,8 +108,45 @@ struct Params {
     #[structopt(long)]
     osm_db: Vec<PathBuf>,
 
+    /// Fill missing fields of OSM addresses using administrative and postal code boundaries
+    /// found in the OSM input files.
+    #[structopt(long)]
+    osm_boundaries: bool,
+
+    /// Store node locations of OSM input files in this file instead of keeping pending objects in
+    /// memory, this reduces memory usage for large input files.
+    #[structopt(long)]
+    osm_node_store: Option<PathBuf>,
+
+    /// Layout of the OSM node store: `dense` for large files (such as a planet file), `sparse`
+    /// otherwise.
+    #[structopt(long, default_value = "sparse")]
+    osm_node_store_kind: importer_osm::NodeStoreKind,
+
+    /// Country code (such as `CZ`) used to select the tag scheme of OSM objects without an
+    /// `addr:country` tag.
+    #[structopt(long)]
+    osm_country: Option<String>,
+
+    /// JSON file overriding the built-in tag schemes, which map OSM tags to address fields.
+    #[structopt(long)]
+    osm_tag_schemes: Option<PathBuf>,
+
+    /// Expand numeric ranges of OSM housenumbers, such as `10-14`, into separate addresses.
+    #[structopt(long)]
+    osm_expand_ranges: bool,
+
+    /// When two OSM addresses are duplicates, keep the most recently edited one.
+    #[structopt(long)]
+    osm_prefer_recent: bool,
+
+    /// What to do with OSM address nodes inside of a building with the same address: `keep-both`,
+    /// `prefer-node` or `prefer-building`.
+    #[structopt(long, default_value = "keep-both")]
+    osm_contained_addresses: importer_osm::ContainedAddresses,
+
     /// While not explicitly disabled with this flag, addresses from france are
-    /// only imported from BANO sources.
+    /// only imported from BANO and BAN sources.
     #[structopt(long)]
     skip_source_filters: bool,
 
@@ -79,7 +182,13 @@ struct Params {
 
 impl Params {
     fn cleanup_empty_paths(mut self) -> Self {
-        for source in [&mut self.bano, &mut self.openaddresses, &mut self.osm] {
+        for source in [
+            &mut self.ban,
+            &mut self.bano,
+            &mut self.nad,
+            &mut self.openaddresses,
+            &mut self.osm,
+        ] {
             source.retain(|path| !path.as_os_str().is_empty());
         }
 
@@ -104,7 +213,9 @@ fn main() -> rusqlite::Result<()> {
 
     let db_sources = None
         .into_iter()
+        .chain(params.ban_db.into_iter().map(|s| (Source::Ban, s)))
         .chain(params.bano_db.into_iter().map(|s| (Source::Bano, s)))
+        .chain(params.nad_db.into_iter().map(|s| (Source::Nad, s)))
         .chain(params.osm_db.into_iter().map(|s| (Source::Osm, s)))
         .chain(
             params
@@ -113,22 +224,28 @@ fn main() -> rusqlite::Result<()> {
                 .map(|s| (Source::OpenAddress, s)),
         );
 
+    // OSM files are imported together so that objects shared by overlapping extracts are only
+    // imported once.
     let raw_sources = None
         .into_iter()
-        .chain(params.bano.into_iter().map(|s| (Source::Bano, s)))
-        .chain(params.osm.into_iter().map(|s| (Source::Osm, s)))
+        .chain(params.ban.into_iter().map(|s| (Source::Ban, vec![s])))
+        .chain(params.bano.into_iter().map(|s| (Source::Bano, vec![s])))
+        .chain(params.nad.into_iter().map(|s| (Source::Nad, vec![s])))
+        .chain(Some((Source::Osm, params.osm)).filter(|(_, paths)| !paths.is_empty()))
         .chain(
             params
                 .openaddresses
                 .into_iter()
-                .map(|s| (Source::OpenAddress, s)),
+                .map(|s| (Source::OpenAddress, vec![s])),
         );
 
     // Load from all sources
 
+    let nb_threads = params.num_threads.unwrap_or_else(num_cpus::get);
+
     let dedupe_config = DedupeConfig {
         refresh_delay: params.refresh_delay,
-        nb_threads: params.num_threads.unwrap_or_else(num_cpus::get),
+        nb_threads,
     };
 
     let mut deduplication = Deduplicator::new(
@@ -137,6 +254,10 @@ fn main() -> rusqlite::Result<()> {
         Some(params.cache_size),
     )?;
 
+    let ranking_config = RankingConfig {
+        prefer_recent_osm: params.osm_prefer_recent,
+    };
+
     for (source, path) in db_sources {
         tprintln!("Loading {:?} addresses from database {:?}...", source, path);
 
@@ -144,26 +265,128 @@ fn main() -> rusqlite::Result<()> {
             &mut deduplication,
             path,
             move |addr| source.filter(addr),
-            move |addr| source.ranking(addr),
+            move |addr| source.ranking_with_config(addr, ranking_config),
             params.refresh_delay,
         )?;
     }
 
-    for (source, path) in raw_sources {
-        tprintln!("Loading {:?} addresses from path {:?}...", source, path);
+    let osm_tag_schemes = params
+        .osm_tag_schemes
+        .map(|path| {
+            importer_osm::TagSchemes::from_file(&path).unwrap_or_else(|err| panic!("{}", err))
+        })
+        .unwrap_or_default();
+
+    let osm_config = importer_osm::ImportConfig {
+        boundaries: params.osm_boundaries,
+        node_store: params
+            .osm_node_store
+            .map(|path| importer_osm::NodeStoreConfig {
+                path,
+                kind: params.osm_node_store_kind,
+            }),
+        tag_schemes: osm_tag_schemes,
+        country: params.osm_country,
+        expand_ranges: params.osm_expand_ranges,
+        contained_addresses: params.osm_contained_addresses,
+        nb_threads: Some(nb_threads),
+        // Streets are not extracted, as only addresses are deduplicated and dumped
+        ..importer_osm::ImportConfig::default()
+    };
+
+    for (source, paths) in raw_sources {
+        tprintln!("Loading {:?} addresses from paths {:?}...", source, paths);
 
         let skip_source_filters = params.skip_source_filters;
         let filter = move |addr: &Address| skip_source_filters || source.filter(addr);
-        let ranking = move |addr: &Address| source.ranking(addr);
+        let ranking = move |addr: &Address| source.ranking_with_config(addr, ranking_config);
         let mut db = deduplication.get_db_inserter(filter, ranking)?;
 
         match source {
-            Source::Osm => importer_osm::import_addresses(&path, &mut db),
-            Source::OpenAddress => importer_openaddresses::import_addresses(path, &mut db),
-            Source::Bano => importer_bano::import_addresses(path, &mut db),
+            Source::Osm => importer_osm::import_extracts(&paths, &mut db, &osm_config),
+            Source::OpenAddress => {
+                for path in paths {
+                    let count =
+                        importer_openaddresses::import_addresses_parallel(path, &db, nb_threads);
+
+                    tprintln!("[OA] Read {} addresses", count);
+                }
+            }
+            Source::Bano => {
+                for path in paths {
+                    importer_bano::import_addresses(path, &mut db)
+                }
+            }
+            Source::Ban => {
+                for path in paths {
+                    importer_ban::import_addresses(path, &mut db)
+                }
+            }
+            Source::Nad => {
+                for path in paths {
+                    importer_nad::import_addresses(path, &mut db)
+                }
+            }
+            Source::Custom { .. } => unreachable!("custom sources have their own configuration"),
         }
     }
 
+    for csv in params.csv {
+        tprintln!("Loading {} addresses from path {:?}...", csv.name, csv.path);
+
+        let config = importer_csv::CsvConfig::from_file(&csv.config)
+            .unwrap_or_else(|err| panic!("invalid mapping file {:?}: {}", csv.config, err));
+
+        let source = Source::Custom {
+            priority: config.priority,
+        };
+
+        let filter = move |addr: &Address| source.filter(addr);
+        let ranking = move |addr: &Address| source.ranking_with_config(addr, ranking_config);
+        let mut db = deduplication.get_db_inserter(filter, ranking)?;
+        importer_csv::import_addresses(&csv.path, &config, &mut db);
+    }
+
+    for geojson in params.geojson {
+        tprintln!(
+            "Loading {} addresses from path {:?}...",
+            geojson.name,
+            geojson.path
+        );
+
+        let config = importer_geojson::GeoJsonConfig::from_file(&geojson.config)
+            .unwrap_or_else(|err| panic!("invalid mapping file {:?}: {}", geojson.config, err));
+
+        let source = Source::Custom {
+            priority: config.priority,
+        };
+
+        let filter = move |addr: &Address| source.filter(addr);
+        let ranking = move |addr: &Address| source.ranking_with_config(addr, ranking_config);
+        let mut db = deduplication.get_db_inserter(filter, ranking)?;
+        importer_geojson::import_addresses(&geojson.path, &config, &mut db);
+    }
+
+    for gpkg in params.gpkg {
+        tprintln!(
+            "Loading {} addresses from path {:?}...",
+            gpkg.name,
+            gpkg.path
+        );
+
+        let config = importer_gpkg::GpkgConfig::from_file(&gpkg.config)
+            .unwrap_or_else(|err| panic!("invalid mapping file {:?}: {}", gpkg.config, err));
+
+        let source = Source::Custom {
+            priority: config.priority,
+        };
+
+        let filter = move |addr: &Address| source.filter(addr);
+        let ranking = move |addr: &Address| source.ranking_with_config(addr, ranking_config);
+        let mut db = deduplication.get_db_inserter(filter, ranking)?;
+        importer_gpkg::import_addresses(&gpkg.path, &config, &mut db);
+    }
+
     // --- Apply deduplication
 
     tprintln!("Deduplication...");
@@ -55,6 +55,7 @@ impl DbHashes {
                     district    TEXT,
                     region      TEXT,
                     postcode    TEXT,
+                    object_kind TEXT,
                     rank        REAL
                 );
 
@@ -120,6 +121,61 @@ impl DbHashes {
         })
     }
 
+    /// Return the addresses located in the bounding box `[min_lon, min_lat, max_lon, max_lat]`.
+    ///
+    /// Addresses are indexed by location the first time this is called, which slows down the
+    /// following insertions.
+    pub fn get_addresses_in_bbox(&self, bbox: [f64; 4]) -> rusqlite::Result<Vec<Address>> {
+        let [min_lon, min_lat, max_lon, max_lat] = bbox;
+        let conn = self.get_conn()?;
+
+        conn.execute_batch(&format!(
+            "CREATE INDEX IF NOT EXISTS {TABLE_ADDRESSES}_location ON {TABLE_ADDRESSES} (lat, lon);"
+        ))?;
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM {TABLE_ADDRESSES} WHERE lat BETWEEN ?1 AND ?2 AND lon BETWEEN ?3 AND ?4;"
+        ))?;
+
+        let mut addr_iter =
+            stmt.query_map([min_lat, max_lat, min_lon, max_lon], |row| row.try_into())?;
+
+        addr_iter.try_fold(Vec::new(), |mut acc, addr| {
+            acc.push(addr?);
+            Ok(acc)
+        })
+    }
+
+    /// Remove the addresses equal to `address`, together with their hashes. Returns the number
+    /// of removed addresses.
+    pub fn remove_address(&self, address: &Address) -> rusqlite::Result<usize> {
+        let conn = self.get_conn()?;
+        let matching = format!(
+            "
+                SELECT id FROM {TABLE_ADDRESSES}
+                WHERE lat = ?1 AND lon = ?2 AND number IS ?3 AND street IS ?4 AND city IS ?5
+            "
+        );
+
+        let params = [
+            &address.lat as &dyn ToSql,
+            &address.lon,
+            &address.number.as_ref().map(|s| s.as_str()),
+            &address.street.as_ref().map(|s| s.as_str()),
+            &address.city.as_ref().map(|s| s.as_str()),
+        ];
+
+        conn.execute(
+            &format!("DELETE FROM {TABLE_HASHES} WHERE address IN ({matching});"),
+            params,
+        )?;
+
+        conn.execute(
+            &format!("DELETE FROM {TABLE_ADDRESSES} WHERE id IN ({matching});"),
+            params,
+        )
+    }
+
     /// Returns the number of rows of a table.
     fn count_table_entries(&self, table: &str) -> rusqlite::Result<i64> {
         self.get_conn()?.query_row(
@@ -361,8 +417,9 @@ impl<'c, 't> Inserter<'c, 't> {
                     district,
                     region,
                     postcode,
+                    object_kind,
                     rank
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10);
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11);
             "
         ))?;
 
@@ -396,6 +453,7 @@ impl<'c, 't> Inserter<'c, 't> {
             &address.district.as_ref().map(|s| s.as_str()),
             &address.region.as_ref().map(|s| s.as_str()),
             &address.postcode.as_ref().map(|s| s.as_str()),
+            &address.meta.object_kind.as_ref().map(|s| s.as_str()),
             &rank,
         ])?;
         Ok(self.tran.last_insert_rowid())
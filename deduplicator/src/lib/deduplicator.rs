@@ -447,6 +447,23 @@ where
         result
     }
 
+    /// Send an address to the worker threads. As channels can be shared, this only requires a
+    /// shared reference and may be called by several producer threads, see `AddressSink`.
+    fn send(&self, addr: Address) {
+        let number = addr.number.as_deref().unwrap_or("");
+
+        if ["", "S/N"].contains(&number.trim()) {
+            // House number is not specified.
+            return;
+        }
+
+        self.addr_sender
+            .as_ref()
+            .expect("failed sending address: transaction is closed")
+            .send(addr)
+            .expect("failed sending address: channel may have closed too early")
+    }
+
     // Wait for all threads to finish (like `borrow_db`, but without performing an action).
     fn flush(&mut self) -> rusqlite::Result<()> {
         self.borrow_db(|_| Ok(()))
@@ -463,24 +480,26 @@ where
     }
 }
 
+/// Addresses can be inserted by several threads at once, for example by importers reading several
+/// files in parallel. The database can't be borrowed meanwhile, as this requires a mutable
+/// reference.
+impl<'db, F, R> tools::AddressSink for DbInserter<'db, F, R>
+where
+    F: Fn(&Address) -> bool + Clone + Send + Sync + 'static,
+    R: Fn(&Address) -> f64 + Clone + Send + Sync + 'static,
+{
+    fn insert(&self, addr: Address) {
+        self.send(addr)
+    }
+}
+
 impl<'db, F, R> tools::CompatibleDB for DbInserter<'db, F, R>
 where
     F: Fn(&Address) -> bool + Clone + Send + 'static,
     R: Fn(&Address) -> f64 + Clone + Send + 'static,
 {
     fn insert(&mut self, addr: Address) {
-        let number = addr.number.as_deref().unwrap_or("");
-
-        if ["", "S/N"].contains(&number.trim()) {
-            // House number is not specified.
-            return;
-        }
-
-        self.addr_sender
-            .as_ref()
-            .expect("failed sending address: transaction is closed")
-            .send(addr)
-            .expect("failed sending address: channel may have closed too early")
+        self.send(addr)
     }
 
     fn get_nb_cities(&mut self) -> i64 {
@@ -501,6 +520,20 @@ where
             .unwrap_or_default()
     }
 
+    // Addresses still handled by the worker threads are written before the query.
+    fn get_addresses_in_bbox(&mut self, bbox: [f64; 4]) -> Vec<Address> {
+        self.borrow_db(|db| db.get_addresses_in_bbox(bbox))
+            .map_err(|err| eprintln!("Error while retrieving addresses in bbox: '{}'", err))
+            .unwrap_or_default()
+    }
+
+    fn remove(&mut self, addr: &Address) {
+        match self.borrow_db(|db| db.remove_address(addr)) {
+            Ok(count) => self.count_addresses -= count as i64,
+            Err(err) => eprintln!("Failed removing address: '{}'", err),
+        }
+    }
+
     // Current implementation for the deduplication actually doesn't log errors.
     fn get_nb_errors(&mut self) -> i64 {
         0
@@ -17,12 +17,40 @@ static FRANCE_SHAPE: Lazy<MultiPolygon<f64>> = Lazy::new(|| {
     shape.try_into().expect("France should be a MultiPolygon")
 });
 
+/// Bounding boxes of the United States and their territories, as
+/// `[min_lon, min_lat, max_lon, max_lat]`.
+const UNITED_STATES: &[[f64; 4]] = &[
+    [-125., 24.4, -66.8, 49.5],    // contiguous states
+    [-180., 51.2, -129.9, 71.5],   // Alaska
+    [172.4, 51.2, 180., 53.1],     // Aleutian Islands beyond the antimeridian
+    [-160.3, 18.9, -154.7, 22.3],  // Hawaii
+    [-67.3, 17.6, -64.5, 18.6],    // Puerto Rico and the Virgin Islands
+    [144.6, 13.2, 146.1, 20.6],    // Guam and the Northern Mariana Islands
+    [-171.1, -14.6, -168.1, -11.], // American Samoa
+];
+
+/// Options of the ranking of addresses, see [`Source::ranking_with_config`].
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RankingConfig {
+    /// Prefer the most recently edited object when two **OpenStreetMap** addresses are
+    /// duplicates.
+    pub prefer_recent_osm: bool,
+}
+
 /// A source of addresses.
 #[derive(Clone, Copy, Debug)]
 pub enum Source {
     Osm,
     OpenAddress,
     Bano,
+    Ban,
+    /// The National Address Database of the United States.
+    Nad,
+    /// A source read by a generic importer, such as the CSV importer, whose priority is given by
+    /// its configuration.
+    Custom {
+        priority: f64,
+    },
 }
 
 impl Source {
@@ -32,15 +60,22 @@ impl Source {
     /// ```
     /// use deduplicator::sources::*;
     ///
-    /// // We expect Bano to have the best reliability and OpenAddress the worst.
+    /// // We expect the BAN to have the best reliability and OpenAddress the worst.
     /// assert!(Source::OpenAddress.priority() < Source::Osm.priority());
     /// assert!(Source::Osm.priority() < Source::Bano.priority());
+    /// assert!(Source::Bano.priority() < Source::Ban.priority());
+    ///
+    /// // The NAD is preferred over OpenAddress and OSM in the United States.
+    /// assert!(Source::Osm.priority() < Source::Nad.priority());
     /// ```
     pub fn priority(self) -> f64 {
         match self {
             Self::OpenAddress => 1.,
             Self::Osm => 2.,
             Self::Bano => 3.,
+            Self::Ban => 4.,
+            Self::Nad => 3.,
+            Self::Custom { priority } => priority,
         }
     }
 
@@ -57,33 +92,194 @@ impl Source {
     ///     ..Address::default()
     /// };
     ///
-    /// // French addresses should only be imported from Bano
+    /// // French addresses should only be imported from Bano or the BAN
     /// assert!(!&Source::OpenAddress.filter(&addr_inside_paris));
     /// assert!(!&Source::Osm.filter(&addr_inside_paris));
     /// assert!(&Source::Bano.filter(&addr_inside_paris));
+    /// assert!(&Source::Ban.filter(&addr_inside_paris));
+    ///
+    /// // NAD addresses are only imported in the United States, which drops the ones with swapped
+    /// // or missing coordinates
+    /// let addr_inside_new_york = Address {
+    ///     lat: 40.7,
+    ///     lon: -74.,
+    ///     ..Address::default()
+    /// };
+    ///
+    /// assert!(&Source::Nad.filter(&addr_inside_new_york));
+    /// assert!(!&Source::Nad.filter(&addr_inside_paris));
+    ///
+    /// // Custom sources are chosen explicitly, so they aren't filtered
+    /// assert!(&Source::Custom { priority: 1. }.filter(&addr_inside_paris));
     /// ```
     pub fn filter(self, address: &Address) -> bool {
         match self {
             Self::Osm | Self::OpenAddress => {
                 !FRANCE_SHAPE.contains(&Point::new(address.lon, address.lat))
             }
-            Self::Bano => true,
+            Self::Nad => UNITED_STATES
+                .iter()
+                .any(|[min_lon, min_lat, max_lon, max_lat]| {
+                    (*min_lon..=*max_lon).contains(&address.lon)
+                        && (*min_lat..=*max_lat).contains(&address.lat)
+                }),
+            Self::Bano | Self::Ban | Self::Custom { .. } => true,
         }
     }
 
     /// Return the ranking of an address that originates from this source.
     ///
+    /// The ranking grows with the number of fields of the address. For **OpenStreetMap**, the kind
+    /// of object the address was read from is used to choose between addresses with the same
+    /// fields, see [`osm_precision`]. For the **BAN**, addresses certified by their municipality
+    /// are preferred, and **BANO** addresses are weighted by their origin, see [`bano_origin`].
+    ///
     /// # Example
     /// ```
     /// use deduplicator::sources::*;
-    /// use tools::Address;
+    /// use tools::{Address, AddressMeta};
     ///
     /// let addr = Address::default();
     ///
     /// // The same address should be taken in priority from OSM
     /// assert!(Source::OpenAddress.ranking(&addr) < Source::Osm.ranking(&addr));
+    ///
+    /// // An entrance is preferred over the centroid of a building
+    /// let entrance = Address {
+    ///     meta: AddressMeta {
+    ///         object_kind: Some("node".into()),
+    ///         entrance: Some("main".into()),
+    ///         ..AddressMeta::default()
+    ///     },
+    ///     ..Address::default()
+    /// };
+    /// let building = Address {
+    ///     meta: AddressMeta {
+    ///         object_kind: Some("way".into()),
+    ///         building: Some("yes".into()),
+    ///         footprint_area: Some(150.),
+    ///         ..AddressMeta::default()
+    ///     },
+    ///     ..Address::default()
+    /// };
+    /// assert!(Source::Osm.ranking(&building) < Source::Osm.ranking(&entrance));
+    ///
+    /// // A certified BAN address is preferred
+    /// let certified = Address {
+    ///     meta: AddressMeta {
+    ///         certified: Some(true),
+    ///         ..AddressMeta::default()
+    ///     },
+    ///     ..Address::default()
+    /// };
+    /// assert!(Source::Ban.ranking(&addr) < Source::Ban.ranking(&certified));
+    ///
+    /// // A BANO address from OSM is preferred over one from the cadastre
+    /// let from = |origin: &str| Address {
+    ///     meta: AddressMeta {
+    ///         origin: Some(origin.into()),
+    ///         ..AddressMeta::default()
+    ///     },
+    ///     ..Address::default()
+    /// };
+    /// assert!(Source::Bano.ranking(&from("CAD")) < Source::Bano.ranking(&from("OSM")));
     /// ```
     pub fn ranking(self, address: &Address) -> f64 {
-        self.priority() + address.count_non_empty_fields() as f64 / (1. + Address::NB_FIELDS as f64)
+        self.ranking_with_config(address, RankingConfig::default())
+    }
+
+    /// Same as [`Source::ranking`], with extra options given by `config`.
+    ///
+    /// # Example
+    /// ```
+    /// use deduplicator::sources::*;
+    /// use tools::{Address, AddressMeta};
+    ///
+    /// let config = RankingConfig { prefer_recent_osm: true };
+    /// let edited_in = |timestamp, city: Option<&str>| Address {
+    ///     city: city.map(Into::into),
+    ///     meta: AddressMeta {
+    ///         timestamp: Some(timestamp),
+    ///         ..AddressMeta::default()
+    ///     },
+    ///     ..Address::default()
+    /// };
+    ///
+    /// // 2012 and 2022, the most recent edit is preferred even though it has fewer fields
+    /// let old = edited_in(1_325_376_000, Some("Paris"));
+    /// let recent = edited_in(1_640_995_200, None);
+    /// assert!(
+    ///     Source::Osm.ranking_with_config(&old, config)
+    ///         < Source::Osm.ranking_with_config(&recent, config)
+    /// );
+    ///
+    /// // Fields are still compared between objects edited on the same day
+    /// let other = edited_in(1_325_376_000 + 3600, None);
+    /// assert!(
+    ///     Source::Osm.ranking_with_config(&other, config)
+    ///         < Source::Osm.ranking_with_config(&old, config)
+    /// );
+    /// ```
+    pub fn ranking_with_config(self, address: &Address, config: RankingConfig) -> f64 {
+        let mut score = address.count_non_empty_fields() as f64;
+
+        match self {
+            Self::Osm => score += osm_precision(address),
+            Self::Ban if address.meta.certified == Some(true) => score += 0.5,
+            Self::Bano => score += bano_origin(address),
+            _ => {}
+        }
+
+        // The score stays below this value, so that sources never overlap
+        let max_score = 2. + Address::NB_FIELDS as f64;
+
+        if let (Self::Osm, true) = (self, config.prefer_recent_osm) {
+            // The day of the last edit is compared first, the score only breaks ties
+            let day = osm_edit_day(address);
+            return self.priority() + (day * max_score + score) / (OSM_DAYS * max_score);
+        }
+
+        self.priority() + score / max_score
+    }
+}
+
+/// Number of days from the creation of **OpenStreetMap** (2004) to 2100, see [`osm_edit_day`].
+const OSM_DAYS: f64 = 35_064.;
+
+/// Day of the last edit of an **OpenStreetMap** address, counted from the creation of
+/// OpenStreetMap (2004) and below [`OSM_DAYS`], or 0 if it is unknown.
+fn osm_edit_day(address: &Address) -> f64 {
+    const START: i64 = 1_072_915_200; // 2004-01-01
+
+    match address.meta.timestamp {
+        Some(timestamp) => ((timestamp - START).div_euclid(86_400) as f64).clamp(0., OSM_DAYS - 1.),
+        None => 0.,
+    }
+}
+
+/// Score between 0 and 1 describing how precise the location of an **OpenStreetMap** address is,
+/// depending on the object it was read from: entrances come first, then other nodes and finally
+/// the centroid of ways, which gets less precise as the building gets larger.
+fn osm_precision(address: &Address) -> f64 {
+    let meta = &address.meta;
+
+    match meta.object_kind.as_deref() {
+        Some("node") if meta.entrance.is_some() => 0.75,
+        Some("node") => 0.5,
+        Some("way") => 0.25 / (1. + meta.footprint_area.unwrap_or(0.) / 1000.),
+        _ => 0.,
+    }
+}
+
+/// Score between 0 and 1 describing how reliable a **BANO** address is, depending on where BANO
+/// took it from: addresses found in both OpenStreetMap and another source come first, then the
+/// ones only found in OpenStreetMap or in the BAN, and finally the ones only found in the
+/// cadastre.
+fn bano_origin(address: &Address) -> f64 {
+    match address.meta.origin.as_deref() {
+        Some("O+O" | "C+O") => 0.75,
+        Some("OSM" | "BAN") => 0.5,
+        Some("CAD") => 0.25,
+        _ => 0.,
     }
 }
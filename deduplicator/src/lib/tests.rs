@@ -176,6 +176,69 @@ fn csv_is_complete() -> rusqlite::Result<()> {
     Ok(())
 }
 
+/// Check that OSM address nodes inside of a building with the same address can be dropped while
+/// importing into the deduplicator.
+#[test]
+fn osm_contained_addresses() -> rusqlite::Result<()> {
+    let tmp_dir = TempDir::new("output").unwrap();
+    let osm_path = tmp_dir.path().join("building.osm");
+
+    std::fs::write(
+        &osm_path,
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<osm version="0.6">
+  <node id="1" lat="48.8420" lon="2.3580"/>
+  <node id="2" lat="48.8420" lon="2.3590"/>
+  <node id="3" lat="48.8430" lon="2.3590"/>
+  <node id="4" lat="48.8430" lon="2.3580"/>
+  <node id="5" lat="48.8425" lon="2.3585">
+    <tag k="addr:housenumber" v="12"/>
+    <tag k="addr:street" v="Rue Buffon"/>
+  </node>
+  <node id="6" lat="48.8425" lon="2.3585">
+    <tag k="addr:housenumber" v="14"/>
+    <tag k="addr:street" v="Rue Buffon"/>
+  </node>
+  <way id="10">
+    <nd ref="1"/>
+    <nd ref="2"/>
+    <nd ref="3"/>
+    <nd ref="4"/>
+    <nd ref="1"/>
+    <tag k="building" v="yes"/>
+    <tag k="addr:housenumber" v="12"/>
+    <tag k="addr:street" v="Rue Buffon"/>
+  </way>
+</osm>
+"#,
+    )
+    .unwrap();
+
+    let mut dedupe = Deduplicator::new(
+        tmp_dir.path().join("addresses.db"),
+        DedupeConfig::default(),
+        None,
+    )?;
+
+    let config = importer_osm::ImportConfig {
+        contained_addresses: importer_osm::ContainedAddresses::PreferBuilding,
+        ..importer_osm::ImportConfig::default()
+    };
+
+    let mut inserter = dedupe.get_db_inserter(|_| true, |_| 1.)?;
+    importer_osm::import_addresses_with_config(&osm_path, &mut inserter, &config);
+    assert_eq!(inserter.get_nb_addresses(), 2);
+
+    let kept = inserter.get_address(12, "Rue Buffon");
+    assert_eq!(kept.len(), 1);
+    assert_eq!(kept[0].meta.object_kind.as_deref(), Some("way"));
+    assert_eq!(inserter.get_address(14, "Rue Buffon").len(), 1);
+
+    let all = inserter.get_addresses_in_bbox([2.35, 48.84, 2.36, 48.85]);
+    assert_eq!(all.len(), 2);
+    Ok(())
+}
+
 #[test]
 fn test_partition() {
     for min_val in 0..=100 {
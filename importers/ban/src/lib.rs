@@ -0,0 +1,220 @@
+use std::io::BufRead;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv::{ReaderBuilder, StringRecord};
+use tools::{
+    for_each_entry, strip_compression_extension, teprintln, tprintln, Address, AddressMeta,
+    CompatibleDB,
+};
+
+/// Number used by the BAN for addresses which don't have a housenumber.
+const NO_HOUSENUMBER: &str = "99999";
+
+/// Columns of the BAN CSV files which are used by the importer.
+struct Columns {
+    id: usize,
+    number: usize,
+    repetition: usize,
+    street: usize,
+    locality: Option<usize>,
+    postcode: usize,
+    city: usize,
+    lon: usize,
+    lat: usize,
+    certification: Option<usize>,
+}
+
+impl Columns {
+    /// Find the columns in the header of the file, as their order changed between versions of the
+    /// export. `nom_ld` and `certification_commune` are optional.
+    fn from_headers(headers: &StringRecord) -> Result<Self, String> {
+        let find = |name| headers.iter().position(|header| header == name);
+        let require = |name| find(name).ok_or_else(|| format!("missing column `{}`", name));
+
+        Ok(Self {
+            id: require("id")?,
+            number: require("numero")?,
+            repetition: require("rep")?,
+            street: require("nom_voie")?,
+            locality: find("nom_ld"),
+            postcode: require("code_postal")?,
+            city: require("nom_commune")?,
+            lon: require("lon")?,
+            lat: require("lat")?,
+            certification: find("certification_commune"),
+        })
+    }
+}
+
+/// Helper macro to get a non-empty CSV field.
+macro_rules! get {
+    ($index:expr, $records:expr) => {
+        $records
+            .get($index)
+            .map(str::trim)
+            .filter(|x| !x.is_empty())
+    };
+}
+
+/// Helper macro to convert a CSV field into an `f64`.
+macro_rules! get_f64 {
+    ($index:expr, $records:expr) => {
+        match get!($index, $records).and_then(|x| f64::from_str(x).ok()) {
+            Some(x) => x,
+            None => continue,
+        }
+    };
+}
+
+/// The entry point of the BAN (**Base Adresse Nationale**) importer.
+///
+/// * The `file_path` argument is where the BAN CSV file is located. Compressed files, such as the
+///   `adresses-<dept>.csv.gz` exports, and zip archives of CSV files are decompressed on the fly,
+///   see [`tools::for_each_entry`].
+/// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
+///   the data will be stored.
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::DB;
+/// use ban::import_addresses;
+///
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// import_addresses("adresses-75.csv.gz", &mut db);
+/// ```
+pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(file_path: P, db: &mut T) {
+    let file_path = file_path.as_ref();
+    teprintln!("[BAN] Reading `{}`", file_path.display());
+    let count_before = db.get_nb_addresses();
+
+    for_each_entry(file_path, |name, input| {
+        // Archives may hold other files than the CSV files
+        if name == file_path
+            || strip_compression_extension(name).extension() == Some("csv".as_ref())
+        {
+            read_csv(name, input, db)
+        }
+    })
+    .expect("cannot read file");
+
+    let count_after = db.get_nb_addresses();
+    tprintln!(
+        "[BAN] Added {} addresses (total: {})",
+        count_after - count_before,
+        count_after
+    );
+}
+
+/// Read the addresses of a BAN CSV file into `db`.
+fn read_csv<T: CompatibleDB>(file_path: &Path, input: &mut dyn BufRead, db: &mut T) {
+    let mut rdr = ReaderBuilder::new().delimiter(b';').from_reader(input);
+
+    let columns = match rdr
+        .headers()
+        .map_err(|e| e.to_string())
+        .and_then(Columns::from_headers)
+    {
+        Ok(columns) => columns,
+        Err(e) => {
+            teprintln!("[BAN] Invalid header in `{}`: {}", file_path.display(), e);
+            return;
+        }
+    };
+
+    let mut count_no_number = 0;
+
+    for x in rdr.into_records() {
+        let x = match x {
+            Ok(x) => x,
+            Err(e) => {
+                teprintln!("[BAN] Invalid record found: {}", e);
+                continue;
+            }
+        };
+
+        let number = match get!(columns.number, x) {
+            Some(number) if number != NO_HOUSENUMBER => number,
+            _ => {
+                count_no_number += 1;
+                continue;
+            }
+        };
+
+        let number = match get!(columns.repetition, x) {
+            Some(repetition) => format!("{} {}", number, repetition),
+            None => number.to_string(),
+        };
+
+        // Addresses of a lieu-dit don't have a street
+        let street = get!(columns.street, x)
+            .or_else(|| columns.locality.and_then(|locality| get!(locality, x)));
+
+        db.insert(Address {
+            lat: get_f64!(columns.lat, x),
+            lon: get_f64!(columns.lon, x),
+            number: Some(number.into()),
+            street: street.map(|x| x.into()),
+            unit: None,
+            city: get!(columns.city, x).map(|x| x.into()),
+            district: None,
+            region: None,
+            postcode: get!(columns.postcode, x).map(|x| x.into()),
+            source_id: get!(columns.id, x).map(|x| x.into()),
+            meta: AddressMeta {
+                certified: columns
+                    .certification
+                    .and_then(|certification| get!(certification, x))
+                    .map(|certification| certification == "1"),
+                ..AddressMeta::default()
+            },
+        });
+    }
+
+    if count_no_number > 0 {
+        tprintln!(
+            "[BAN] Ignored {} addresses without a housenumber",
+            count_no_number
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tools::DB;
+
+    const INPUT: &str = "\
+id;id_fantoir;numero;rep;nom_voie;code_postal;code_insee;nom_commune;lon;lat;nom_ld;certification_commune
+75105_1348_00012;75105_1348;12;bis;Rue Buffon;75005;75105;Paris;2.3585;48.8427;;1
+75105_1348_00014;75105_1348;14;;Rue Buffon;75005;75105;Paris;2.3590;48.8425;;0
+01001_b002_00003;01001_b002;3;;;01400;01001;L'Abergement;4.9210;46.1532;Les Granges;
+01001_b002_99999;01001_b002;99999;;;01400;01001;L'Abergement;4.9200;46.1530;Les Granges;
+";
+
+    #[test]
+    fn check_import() {
+        let csv_file = std::env::temp_dir().join("check_ban_import.csv");
+        let db_file = "check_ban_import.db";
+        std::fs::write(&csv_file, INPUT).expect("failed to write input");
+
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        import_addresses(&csv_file, &mut db);
+        assert_eq!(db.get_nb_addresses(), 3);
+
+        let address = db.remove_by_source_id("75105_1348_00012").pop();
+        let address = address.expect("missing address");
+        assert_eq!(address.number.as_deref(), Some("12 bis"));
+        assert_eq!(address.street.as_deref(), Some("Rue Buffon"));
+        assert_eq!(address.meta.certified, Some(true));
+
+        let address = db.remove_by_source_id("01001_b002_00003").pop();
+        let address = address.expect("missing address");
+        assert_eq!(address.street.as_deref(), Some("Les Granges"));
+        assert_eq!(address.meta.certified, None);
+
+        let _ = std::fs::remove_file(csv_file); // we ignore any potential error
+        let _ = std::fs::remove_file(db_file);
+    }
+}
@@ -1,13 +1,118 @@
-use std::fs::File;
-use std::io::BufReader;
+use std::io::BufRead;
 use std::path::Path;
 use std::str::FromStr;
 
-use csv::ReaderBuilder;
-use tools::{teprintln, tprintln, Address, CompatibleDB};
+use csv::{ReaderBuilder, StringRecord};
+use tools::{
+    for_each_entry, strip_compression_extension, teprintln, tprintln, Address, AddressMeta,
+    CompatibleDB,
+};
 
-/// Size of the read buffer put on top of the input PBF file
-const CSV_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+/// Columns of a BANO CSV file.
+#[derive(Debug, PartialEq)]
+struct Columns {
+    id: usize,
+    number: usize,
+    street: usize,
+    postcode: usize,
+    city: usize,
+    origin: Option<usize>,
+    lat: usize,
+    lon: usize,
+}
+
+impl Columns {
+    /// Layout of the BANO files without a header: `id`, `numero`, `voie`, `code_post`,
+    /// `nom_comm`, `source`, `lat` and `lon`.
+    const DEFAULT: Self = Self {
+        id: 0,
+        number: 1,
+        street: 2,
+        postcode: 3,
+        city: 4,
+        origin: Some(5),
+        lat: 6,
+        lon: 7,
+    };
+
+    /// Detect the layout of a file from its first record. Returns the columns, and whether the
+    /// first record is a header. An error is returned when the first record is neither a known
+    /// header, nor an address with the default layout.
+    fn detect(first: &StringRecord) -> Result<(Self, bool), String> {
+        let find = |names: &[&str]| {
+            first.iter().position(|field| {
+                names
+                    .iter()
+                    .any(|name| field.trim().eq_ignore_ascii_case(name))
+            })
+        };
+
+        if let (Some(lat), Some(lon)) = (find(&["lat"]), find(&["lon"])) {
+            let require = |names: &[&str]| {
+                find(names).ok_or_else(|| format!("missing column `{}` in header", names[0]))
+            };
+
+            let columns = Self {
+                id: require(&["id"])?,
+                number: require(&["numero"])?,
+                street: require(&["voie", "nom_voie"])?,
+                postcode: require(&["code_post", "code_postal", "cp"])?,
+                city: require(&["nom_comm", "nom_commune", "ville"])?,
+                origin: find(&["source"]),
+                lat,
+                lon,
+            };
+
+            return Ok((columns, true));
+        }
+
+        let is_float = |index| {
+            first
+                .get(index)
+                .is_some_and(|field| f64::from_str(field).is_ok())
+        };
+
+        let is_default = first.len() == 8
+            && first.get(Self::DEFAULT.id).is_some_and(is_bano_id)
+            && first.get(Self::DEFAULT.postcode).is_some_and(is_postcode)
+            && is_float(Self::DEFAULT.lat)
+            && is_float(Self::DEFAULT.lon);
+
+        if is_default {
+            Ok((Self::DEFAULT, false))
+        } else {
+            Err(format!(
+                "unknown layout with {} columns: {:?}",
+                first.len(),
+                first
+            ))
+        }
+    }
+}
+
+/// Check if a field is a BANO id, which starts with the INSEE code of the municipality (such as
+/// `75105`, or `2A004` in Corsica), followed by the identifier of the address.
+fn is_bano_id(field: &str) -> bool {
+    let (Some(department), Some(municipality), Some(rest)) =
+        (field.get(..2), field.get(2..5), field.get(5..))
+    else {
+        return false;
+    };
+
+    let is_digits = |value: &str| value.bytes().all(|byte| byte.is_ascii_digit());
+
+    (is_digits(department) || department == "2A" || department == "2B")
+        && is_digits(municipality)
+        && !rest.is_empty()
+        && rest
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'-')
+}
+
+/// Check if a field is a French postcode, made of 5 digits.
+fn is_postcode(field: &str) -> bool {
+    field.len() == 5 && field.bytes().all(|byte| byte.is_ascii_digit())
+}
 
 /// Helper macro to convert a CSV field into a `String`.
 macro_rules! get {
@@ -28,7 +133,9 @@ macro_rules! get_f64 {
 
 /// The entry point of the BANO importer.
 ///
-/// * The `file_path` argument is where the BANO CSV file is located.
+/// * The `file_path` argument is where the BANO CSV file is located. Its layout is detected from
+///   its first line, which may be a header. It may be compressed, or be a zip archive of CSV
+///   files, see [`tools::for_each_entry`].
 /// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
 ///   the data will be stored.
 ///
@@ -42,17 +149,55 @@ macro_rules! get_f64 {
 /// import_addresses("somefile.csv", &mut db);
 /// ```
 pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(file_path: P, db: &mut T) {
-    teprintln!("[BANO] Reading `{}`", file_path.as_ref().display());
+    let file_path = file_path.as_ref();
+    teprintln!("[BANO] Reading `{}`", file_path.display());
     let count_before = db.get_nb_addresses();
 
-    let file = BufReader::with_capacity(
-        CSV_BUFFER_SIZE,
-        File::open(file_path).expect("cannot open file"),
+    for_each_entry(file_path, |name, input| {
+        // Archives may hold other files than the CSV files
+        if name == file_path
+            || strip_compression_extension(name).extension() == Some("csv".as_ref())
+        {
+            read_csv(name, input, db)
+        }
+    })
+    .expect("cannot read file");
+
+    let count_after = db.get_nb_addresses();
+    tprintln!(
+        "[BANO] Added {} addresses (total: {})",
+        count_after - count_before,
+        count_after
     );
+}
 
-    let rdr = ReaderBuilder::new().has_headers(false).from_reader(file);
+/// Read the addresses of a BANO CSV file into `db`.
+fn read_csv<T: CompatibleDB>(file_path: &Path, input: &mut dyn BufRead, db: &mut T) {
+    let mut records = ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(input)
+        .into_records();
+
+    let first = match records.next() {
+        Some(Ok(first)) => first,
+        Some(Err(e)) => {
+            teprintln!("[BANO] Invalid record found: {}", e);
+            return;
+        }
+        None => return,
+    };
 
-    for x in rdr.into_records() {
+    let (columns, has_header) = match Columns::detect(&first) {
+        Ok(layout) => layout,
+        Err(e) => {
+            teprintln!("[BANO] Cannot read `{}`: {}", file_path.display(), e);
+            return;
+        }
+    };
+
+    let first = (!has_header).then_some(Ok(first));
+
+    for x in first.into_iter().chain(records) {
         let x = match x {
             Ok(x) => x,
             Err(e) => {
@@ -62,22 +207,86 @@ pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(file_path: P, db: &mut
         };
 
         db.insert(Address {
-            lat: get_f64!(6, x),
-            lon: get_f64!(7, x),
-            number: get!(1, x).map(|x| x.into()),
-            street: get!(2, x).map(|x| x.into()),
+            lat: get_f64!(columns.lat, x),
+            lon: get_f64!(columns.lon, x),
+            number: get!(columns.number, x).map(|x| x.into()),
+            street: get!(columns.street, x).map(|x| x.into()),
             unit: None,
-            city: get!(4, x).map(|x| x.into()),
+            city: get!(columns.city, x).map(|x| x.into()),
             district: None,
             region: None,
-            postcode: get!(3, x).map(|x| x.into()),
+            postcode: get!(columns.postcode, x).map(|x| x.into()),
+            source_id: get!(columns.id, x).map(|x| x.into()),
+            meta: AddressMeta {
+                origin: columns
+                    .origin
+                    .and_then(|origin| get!(origin, x))
+                    .map(|origin| origin.into()),
+                ..AddressMeta::default()
+            },
         });
     }
+}
 
-    let count_after = db.get_nb_addresses();
-    tprintln!(
-        "[BANO] Added {} addresses (total: {})",
-        count_after - count_before,
-        count_after
-    );
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_detect() {
+        let data = StringRecord::from(vec![
+            "751050001A-12",
+            "12",
+            "Rue Buffon",
+            "75005",
+            "Paris",
+            "CAD",
+            "48.8427",
+            "2.3585",
+        ]);
+        assert_eq!(Columns::detect(&data), Ok((Columns::DEFAULT, false)));
+
+        let header = StringRecord::from(vec![
+            "id", "numero", "voie", "cp", "ville", "source", "lon", "lat",
+        ]);
+        let (columns, has_header) = Columns::detect(&header).unwrap();
+        assert!(has_header);
+        assert_eq!((columns.lon, columns.lat), (6, 7));
+        assert_eq!(columns.origin, Some(5));
+
+        let header = StringRecord::from(vec!["id", "numero", "lat", "lon"]);
+        assert!(Columns::detect(&header).is_err());
+
+        let other = StringRecord::from(vec!["12", "Rue Buffon", "48.8427", "2.3585"]);
+        assert!(Columns::detect(&other).is_err());
+
+        // Reordered columns which still have floats in the last two columns
+        let reordered = StringRecord::from(vec![
+            "12",
+            "751050001A-12",
+            "Rue Buffon",
+            "Paris",
+            "75005",
+            "CAD",
+            "48.8427",
+            "2.3585",
+        ]);
+        assert!(Columns::detect(&reordered).is_err());
+
+        let reordered = StringRecord::from(vec![
+            "751050001A-12",
+            "12",
+            "Rue Buffon",
+            "Paris",
+            "75005",
+            "CAD",
+            "48.8427",
+            "2.3585",
+        ]);
+        assert!(Columns::detect(&reordered).is_err());
+
+        assert!(is_bano_id("2A0040010B-3"));
+        assert!(!is_bano_id("75105"));
+        assert!(!is_bano_id("Rue Buffon"));
+    }
 }
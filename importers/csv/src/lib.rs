@@ -0,0 +1,362 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+use csv::{ReaderBuilder, StringRecord};
+use serde::Deserialize;
+use smartstring::alias::String;
+use tools::{
+    for_each_entry, strip_compression_extension, teprintln, tprintln, AddressMapping, Column,
+    CompatibleDB, Crs, Record,
+};
+
+/// Extensions of the files read from zip archives.
+const EXTENSIONS: &[&str] = &["csv", "tsv", "txt"];
+
+fn default_delimiter() -> char {
+    ','
+}
+
+fn default_has_headers() -> bool {
+    true
+}
+
+fn default_priority() -> f64 {
+    1.
+}
+
+/// Configuration of the importer, read from a mapping file, see [`CsvConfig::from_file`].
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct CsvConfig {
+    /// Separator of the fields, a comma by default.
+    #[serde(default = "default_delimiter")]
+    pub delimiter: char,
+    /// Whether the first line of files is a header, which is the case by default. Columns can
+    /// only be found by their index when there is none.
+    #[serde(default = "default_has_headers")]
+    pub has_headers: bool,
+    /// Coordinate reference system of the coordinates, WGS84 (`EPSG:4326`) by default. Projected
+    /// coordinates are read with `lon` as the easting and `lat` as the northing, see [`Crs`].
+    #[serde(default)]
+    pub crs: Crs,
+    /// Base priority of the source in the deduplicator, which keeps the address of the source with
+    /// the greatest priority among duplicates. Sources which have their own importer have a
+    /// priority between 1 (OpenAddresses) and 4 (the BAN).
+    #[serde(default = "default_priority")]
+    pub priority: f64,
+    /// Mapping of the columns to the fields of addresses.
+    pub fields: AddressMapping,
+}
+
+impl CsvConfig {
+    /// Read the configuration from a TOML file, such as:
+    ///
+    /// ```toml
+    /// delimiter = ";"
+    /// priority = 2.5
+    ///
+    /// [fields]
+    /// lon = "x"
+    /// lat = "y"
+    /// number = ["numero", "suffixe"]
+    /// street = "voie"
+    /// postcode = "cp"
+    /// city = "commune"
+    /// country = { value = "FR" }
+    /// ```
+    ///
+    /// Columns are given by their name in the header, or by their index (starting at 0). A field
+    /// may be built from several columns, which are joined with spaces, or with another separator
+    /// (`{ columns = ["numero", "suffixe"], separator = "" }`), or be a constant.
+    pub fn from_file(path: &Path) -> Result<Self, std::string::String> {
+        let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_toml(&content)
+    }
+
+    /// Read the configuration from the content of a mapping file, see [`CsvConfig::from_file`].
+    pub fn from_toml(content: &str) -> Result<Self, std::string::String> {
+        let config: Self = toml::from_str(content).map_err(|err| err.to_string())?;
+        config.check()?;
+        Ok(config)
+    }
+
+    /// Check that the configuration can be used to read files.
+    fn check(&self) -> Result<(), std::string::String> {
+        if !self.delimiter.is_ascii() {
+            return Err(format!(
+                "delimiter must be an ASCII character, not {:?}",
+                self.delimiter
+            ));
+        }
+
+        if self.fields.lat.is_none() || self.fields.lon.is_none() {
+            return Err("fields `lat` and `lon` are required".to_string());
+        }
+
+        let by_name = self
+            .fields
+            .fields()
+            .flat_map(|field| field.columns())
+            .any(|column| matches!(column, Column::Name(_)));
+
+        if by_name && !self.has_headers {
+            return Err("columns can only be found by their name in files with a header".into());
+        }
+
+        Ok(())
+    }
+}
+
+/// A row of a CSV file, whose columns may be found by their name in the header.
+struct Row<'a> {
+    header: &'a HashMap<String, usize>,
+    record: &'a StringRecord,
+}
+
+impl<'a> Record for Row<'a> {
+    fn get(&self, column: &Column) -> Option<Cow<'_, str>> {
+        let index = match column {
+            Column::Index(index) => *index,
+            Column::Name(name) => *self.header.get(name)?,
+        };
+
+        self.record.get(index).map(Cow::Borrowed)
+    }
+}
+
+/// The entry point of the generic CSV importer.
+///
+/// * The `file_path` argument is where the CSV file is located. It may be compressed, or be a zip
+///   archive of CSV files, see [`tools::for_each_entry`].
+/// * The `config` argument tells how to read the file, and how its columns are mapped to the fields
+///   of addresses, see [`CsvConfig::from_file`].
+/// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
+///   the data will be stored.
+///
+/// Example:
+///
+/// ```no_run
+/// use std::path::Path;
+/// use tools::DB;
+/// use csv_addresses::{import_addresses, CsvConfig};
+///
+/// let config = CsvConfig::from_file(Path::new("mapping.toml")).expect("invalid mapping file");
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// import_addresses("somefile.csv", &config, &mut db);
+/// ```
+pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(
+    file_path: P,
+    config: &CsvConfig,
+    db: &mut T,
+) {
+    let file_path = file_path.as_ref();
+    teprintln!("[CSV] Reading `{}`", file_path.display());
+    let count_before = db.get_nb_addresses();
+
+    for_each_entry(file_path, |name, input| {
+        // Archives may hold other files than the CSV files
+        let stripped = strip_compression_extension(name);
+        let extension = stripped
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+
+        if name == file_path || EXTENSIONS.contains(&extension) {
+            read_csv(name, input, config, db)
+        }
+    })
+    .expect("cannot read file");
+
+    let count_after = db.get_nb_addresses();
+    tprintln!(
+        "[CSV] Added {} addresses (total: {})",
+        count_after - count_before,
+        count_after
+    );
+}
+
+/// Read the addresses of a CSV file into `db`.
+fn read_csv<T: CompatibleDB>(
+    file_path: &Path,
+    input: &mut dyn BufRead,
+    config: &CsvConfig,
+    db: &mut T,
+) {
+    let mut reader = ReaderBuilder::new()
+        .delimiter(config.delimiter as u8)
+        .has_headers(config.has_headers)
+        .flexible(true)
+        .from_reader(input);
+
+    let mut header = HashMap::new();
+
+    if config.has_headers {
+        let names = match reader.headers() {
+            Ok(names) => names,
+            Err(e) => {
+                teprintln!(
+                    "[CSV] Cannot read header of `{}`: {}",
+                    file_path.display(),
+                    e
+                );
+                return;
+            }
+        };
+
+        header = names
+            .iter()
+            .enumerate()
+            .map(|(index, name)| (name.trim().into(), index))
+            .collect();
+
+        let missing = config
+            .fields
+            .fields()
+            .flat_map(|field| field.columns())
+            .find(|column| matches!(column, Column::Name(name) if !header.contains_key(name)));
+
+        if let Some(column) = missing {
+            teprintln!(
+                "[CSV] Cannot read `{}`: missing column {:?} in header",
+                file_path.display(),
+                column
+            );
+            return;
+        }
+    }
+
+    let mut count_invalid = 0;
+
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                teprintln!("[CSV] Invalid record found: {}", e);
+                continue;
+            }
+        };
+
+        let row = Row {
+            header: &header,
+            record: &record,
+        };
+
+        let address = config.fields.address(&row, None).and_then(|mut address| {
+            [address.lon, address.lat] = config.crs.to_wgs84(address.lon, address.lat).ok()?;
+            Some(address)
+        });
+
+        match address {
+            Some(address) => db.insert(address),
+            None => count_invalid += 1,
+        }
+    }
+
+    if count_invalid > 0 {
+        teprintln!(
+            "[CSV] Skipped {} rows without valid coordinates in `{}`",
+            count_invalid,
+            file_path.display()
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tools::DB;
+
+    #[test]
+    fn check_config() {
+        let config = CsvConfig::from_toml(
+            r#"
+                delimiter = ";"
+                priority = 2.5
+
+                [fields]
+                lon = "x"
+                lat = "y"
+                number = { columns = ["numero", "suffixe"], separator = "" }
+                street = "voie"
+                country = { value = "FR" }
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(config.delimiter, ';');
+        assert!(config.has_headers);
+        assert_eq!(config.priority, 2.5);
+        assert_eq!(config.fields.fields().count(), 5);
+        assert_eq!(config.crs, Crs::Wgs84);
+
+        let config =
+            CsvConfig::from_toml("crs = \"EPSG:2154\"\n[fields]\nlon = \"x\"\nlat = \"y\"")
+                .unwrap();
+        assert_eq!(config.crs, Crs::Lambert93);
+
+        let invalid = [
+            // Missing coordinates
+            "[fields]\nlon = \"x\"",
+            // Unknown field
+            "[fields]\nlon = \"x\"\nlat = \"y\"\nhousenumber = \"n\"",
+            // Names without a header
+            "has_headers = false\n[fields]\nlon = 0\nlat = \"y\"",
+            // Unsupported coordinate reference system
+            "crs = \"EPSG:3857\"\n[fields]\nlon = \"x\"\nlat = \"y\"",
+        ];
+
+        for content in invalid {
+            assert!(CsvConfig::from_toml(content).is_err(), "{}", content);
+        }
+    }
+
+    #[test]
+    fn check_import() {
+        let config = CsvConfig::from_toml(
+            r#"
+                delimiter = "\t"
+                has_headers = false
+
+                [fields]
+                lon = 3
+                lat = 2
+                number = [0, 1]
+                street = 4
+                postcode = 5
+                city = 6
+            "#,
+        )
+        .unwrap();
+
+        let data = "12\tbis\t48.8427\t2.3585\tRue Buffon\t75005\tParis\n\
+                    14\t\t48,8428\t2,3586\tRue Buffon\t75005\tParis\n\
+                    16\t\t\t\tRue Buffon\t75005\tParis\n";
+
+        let db_file = "check_import.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        read_csv(
+            Path::new("test.tsv"),
+            &mut data.as_bytes(),
+            &config,
+            &mut db,
+        );
+        assert_eq!(db.get_nb_addresses(), 2);
+
+        let addr = db.get_address(14, "Rue Buffon");
+        assert_eq!((addr[0].lon, addr[0].lat), (2.3586, 48.8428));
+        assert_eq!(addr[0].postcode.as_deref(), Some("75005"));
+
+        let record =
+            StringRecord::from(data.lines().next().unwrap().split('\t').collect::<Vec<_>>());
+        let row = Row {
+            header: &HashMap::new(),
+            record: &record,
+        };
+        let addr = config.fields.address(&row, None).unwrap();
+        assert_eq!(addr.number.as_deref(), Some("12 bis"));
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+}
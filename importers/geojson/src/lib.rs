@@ -0,0 +1,458 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::io::{self, BufRead, Read};
+use std::path::Path;
+
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use tools::{
+    for_each_entry, representative_point, strip_compression_extension, teprintln, tprintln,
+    AddressMapping, Column, CompatibleDB, Record, Rejection, Rejections,
+};
+
+/// Extensions of the files read from zip archives.
+const EXTENSIONS: &[&str] = &[
+    "geojson",
+    "geojsonl",
+    "geojsons",
+    "geojsonseq",
+    "json",
+    "jsonl",
+    "ndjson",
+];
+
+/// Separator of the features of GeoJSON text sequences (RFC 8142), which are read as whitespace.
+const RECORD_SEPARATOR: u8 = 0x1e;
+
+fn default_priority() -> f64 {
+    1.
+}
+
+/// Configuration of the importer, read from a mapping file, see [`GeoJsonConfig::from_file`].
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GeoJsonConfig {
+    /// Base priority of the source in the deduplicator, 1 by default, like OpenAddresses.
+    #[serde(default = "default_priority")]
+    pub priority: f64,
+    /// Mapping of the properties of features to the fields of addresses.
+    pub fields: AddressMapping,
+}
+
+impl GeoJsonConfig {
+    /// Read the configuration from a TOML file, such as:
+    ///
+    /// ```toml
+    /// priority = 2.5
+    ///
+    /// [fields]
+    /// number = ["house_number", "suffix"]
+    /// street = "street_name"
+    /// postcode = "zip"
+    /// city = "municipality"
+    /// country = { value = "DK" }
+    /// ```
+    ///
+    /// Properties are given by their name. A field may be built from several properties, which are
+    /// joined with spaces, or with another separator
+    /// (`{ columns = ["house_number", "suffix"], separator = "" }`), or be a constant. The location
+    /// of addresses is taken from the geometry of features, unless `lat` and `lon` are mapped.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_toml(&content)
+    }
+
+    /// Read the configuration from the content of a mapping file, see
+    /// [`GeoJsonConfig::from_file`].
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(content).map_err(|err| err.to_string())?;
+
+        if config.fields.lat.is_some() != config.fields.lon.is_some() {
+            return Err("fields `lat` and `lon` must be mapped together".to_string());
+        }
+
+        let by_index = config
+            .fields
+            .fields()
+            .flat_map(|field| field.columns())
+            .any(|column| matches!(column, Column::Index(_)));
+
+        if by_index {
+            return Err("properties can only be found by their name".to_string());
+        }
+
+        Ok(config)
+    }
+}
+
+/// A feature, with the members used by the importer.
+struct Feature {
+    properties: Map<String, Value>,
+    geometry: Option<Value>,
+}
+
+impl Feature {
+    /// Build a feature from its members, it is rejected if its properties aren't an object.
+    fn new(properties: Value, geometry: Option<Value>) -> Result<Self, Rejection> {
+        let properties = match properties {
+            Value::Object(properties) => properties,
+            Value::Null => Map::new(),
+            _ => return Err(Rejection::InvalidRecord),
+        };
+
+        Ok(Self {
+            properties,
+            geometry,
+        })
+    }
+}
+
+/// The properties of a feature. Numbers and booleans are read as strings.
+struct Properties<'a>(&'a Map<String, Value>);
+
+impl<'a> Record for Properties<'a> {
+    fn get(&self, column: &Column) -> Option<Cow<'_, str>> {
+        let Column::Name(name) = column else {
+            return None;
+        };
+
+        match self.0.get(name.as_str())? {
+            Value::String(value) => Some(Cow::Borrowed(value)),
+            Value::Number(value) => Some(Cow::Owned(value.to_string())),
+            Value::Bool(value) => Some(Cow::Owned(value.to_string())),
+            _ => None,
+        }
+    }
+}
+
+/// Deserializes a GeoJSON object and calls `on_feature` with each feature it holds: the object
+/// itself if it is a feature, or its `features` if it is a collection. Features are read one after
+/// the other, so that large collections aren't loaded in memory. Invalid features are given as
+/// rejections, so that the features following them are still read.
+struct Features<'f, F>(&'f mut F);
+
+impl<'de, 'f, F: FnMut(Result<Feature, Rejection>)> DeserializeSeed<'de> for Features<'f, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de, 'f, F: FnMut(Result<Feature, Rejection>)> Visitor<'de> for Features<'f, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a GeoJSON feature or feature collection")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        let on_feature = self.0;
+        let mut kind = None;
+        let mut properties = Value::Null;
+        let mut geometry = None;
+
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "type" => kind = Some(map.next_value::<String>()?),
+                "features" => map.next_value_seed(FeatureList(&mut *on_feature))?,
+                "properties" => properties = map.next_value()?,
+                "geometry" => geometry = map.next_value()?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+
+        if kind.as_deref() == Some("Feature") {
+            on_feature(Feature::new(properties, geometry));
+        }
+
+        Ok(())
+    }
+}
+
+/// Deserializes the `features` of a collection, see [`Features`].
+struct FeatureList<'f, F>(&'f mut F);
+
+impl<'de, 'f, F: FnMut(Result<Feature, Rejection>)> DeserializeSeed<'de> for FeatureList<'f, F> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'f, F: FnMut(Result<Feature, Rejection>)> Visitor<'de> for FeatureList<'f, F> {
+    type Value = ();
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a list of GeoJSON features")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        // Features are read one at a time, so that one which isn't an object doesn't end the list
+        while let Some(feature) = seq.next_element::<Value>()? {
+            if Features(&mut *self.0).deserialize(feature).is_err() {
+                (self.0)(Err(Rejection::InvalidRecord));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the separators of GeoJSON text sequences as spaces, so that they can be read as a
+/// stream of JSON values like newline-delimited GeoJSON.
+struct SkipRecordSeparators<R>(R);
+
+impl<R: Read> Read for SkipRecordSeparators<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.0.read(buf)?;
+
+        for byte in &mut buf[..len] {
+            if *byte == RECORD_SEPARATOR {
+                *byte = b' ';
+            }
+        }
+
+        Ok(len)
+    }
+}
+
+/// The entry point of the generic GeoJSON importer.
+///
+/// * The `file_path` argument is where the GeoJSON file is located. It may hold a feature
+///   collection, or a sequence of features such as newline-delimited GeoJSON (GeoJSONSeq). It may
+///   be compressed, or be a zip archive of GeoJSON files, see [`tools::for_each_entry`].
+/// * The `config` argument tells how the properties of features are mapped to the fields of
+///   addresses, see [`GeoJsonConfig::from_file`].
+/// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
+///   the data will be stored.
+///
+/// The location of addresses is taken from the geometry of features: points are used as they are,
+/// and a representative point is used for other geometries, see [`tools::representative_point`].
+///
+/// Example:
+///
+/// ```no_run
+/// use std::path::Path;
+/// use tools::DB;
+/// use geojson_addresses::{import_addresses, GeoJsonConfig};
+///
+/// let config = GeoJsonConfig::from_file(Path::new("mapping.toml")).expect("invalid mapping file");
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// import_addresses("somefile.geojson", &config, &mut db);
+/// ```
+pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(
+    file_path: P,
+    config: &GeoJsonConfig,
+    db: &mut T,
+) {
+    let file_path = file_path.as_ref();
+    teprintln!("[GeoJSON] Reading `{}`", file_path.display());
+    let count_before = db.get_nb_addresses();
+
+    for_each_entry(file_path, |name, input| {
+        // Archives may hold other files than the GeoJSON files
+        let stripped = strip_compression_extension(name);
+        let extension = stripped
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+
+        if name == file_path || EXTENSIONS.contains(&extension) {
+            read_geojson(name, input, config, db)
+        }
+    })
+    .expect("cannot read file");
+
+    let count_after = db.get_nb_addresses();
+    tprintln!(
+        "[GeoJSON] Added {} addresses (total: {})",
+        count_after - count_before,
+        count_after
+    );
+}
+
+/// Read the addresses of a GeoJSON file into `db`.
+fn read_geojson<T: CompatibleDB>(
+    file_path: &Path,
+    input: &mut dyn BufRead,
+    config: &GeoJsonConfig,
+    db: &mut T,
+) {
+    let mapped_position = config.fields.lat.is_some();
+    let mut rejections = Rejections::default();
+
+    let mut on_feature = |feature: Result<Feature, Rejection>| {
+        let feature = match feature {
+            Ok(feature) => feature,
+            Err(reason) => {
+                rejections.add(reason);
+                return;
+            }
+        };
+
+        let position = if mapped_position {
+            None
+        } else {
+            let geometry = feature.geometry.as_ref().unwrap_or(&Value::Null);
+
+            let Some(kind) = geometry.get("type").and_then(Value::as_str) else {
+                rejections.add(Rejection::MissingGeometry);
+                return;
+            };
+
+            let coordinates = geometry.get("coordinates").unwrap_or(&Value::Null);
+
+            match representative_point(kind, coordinates) {
+                Ok(position) => Some(position),
+                Err(err) => {
+                    rejections.add(err.into());
+                    return;
+                }
+            }
+        };
+
+        match config
+            .fields
+            .address(&Properties(&feature.properties), position)
+        {
+            Some(address) => db.insert(address),
+            None => rejections.add(Rejection::InvalidCoordinates),
+        }
+    };
+
+    let mut deserializer = serde_json::Deserializer::from_reader(SkipRecordSeparators(input));
+
+    loop {
+        match Features(&mut on_feature).deserialize(&mut deserializer) {
+            Ok(()) => {}
+            // There is no other value in the file
+            Err(err) if err.is_eof() => break,
+            Err(err) => {
+                teprintln!(
+                    "[GeoJSON] Invalid content found in `{}`: {}",
+                    file_path.display(),
+                    err
+                );
+                break;
+            }
+        }
+    }
+
+    rejections.log_skipped("GeoJSON", file_path.display());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tools::DB;
+
+    const MAPPING: &str = r#"
+        [fields]
+        number = { columns = ["husnr", "bogstav"], separator = "" }
+        street = "vejnavn"
+        postcode = "postnr"
+        country = { value = "DK" }
+    "#;
+
+    fn import(db_file: &str, data: &str) -> DB {
+        let config = GeoJsonConfig::from_toml(MAPPING).unwrap();
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        read_geojson(
+            Path::new("test.geojson"),
+            &mut data.as_bytes(),
+            &config,
+            &mut db,
+        );
+        db
+    }
+
+    #[test]
+    fn check_config() {
+        assert_eq!(GeoJsonConfig::from_toml(MAPPING).unwrap().priority, 1.);
+        let config = GeoJsonConfig::from_toml(&format!("priority = 2.5\n{}", MAPPING)).unwrap();
+        assert_eq!(config.priority, 2.5);
+        assert!(GeoJsonConfig::from_toml("[fields]\nlat = \"y\"").is_err());
+        assert!(GeoJsonConfig::from_toml("[fields]\nstreet = 2").is_err());
+    }
+
+    #[test]
+    fn check_collection() {
+        let db_file = "check_collection.db";
+        let mut db = import(
+            db_file,
+            r#"{
+                "type": "FeatureCollection",
+                "name": "adresser",
+                "features": [
+                    {
+                        "type": "Feature",
+                        "properties": { "husnr": 12, "bogstav": "A", "vejnavn": "Vesterbrogade", "postnr": "1620" },
+                        "geometry": { "type": "Point", "coordinates": [12.55, 55.67] }
+                    },
+                    {
+                        "type": "Feature",
+                        "properties": { "husnr": "14", "vejnavn": "Vesterbrogade", "postnr": null },
+                        "geometry": {
+                            "type": "Polygon",
+                            "coordinates": [[[12.0, 55.0], [12.2, 55.0], [12.2, 55.2], [12.0, 55.2], [12.0, 55.0]]]
+                        }
+                    },
+                    {
+                        "type": "Feature",
+                        "properties": { "husnr": "16", "vejnavn": "Vesterbrogade" },
+                        "geometry": null
+                    },
+                    "Vesterbrogade 18",
+                    {
+                        "type": "Feature",
+                        "properties": "Vesterbrogade 20",
+                        "geometry": { "type": "Point", "coordinates": [12.54, 55.67] }
+                    },
+                    {
+                        "type": "Feature",
+                        "properties": { "husnr": "22", "vejnavn": "Vesterbrogade" },
+                        "geometry": { "type": "Point", "coordinates": [12.53, 55.67] }
+                    }
+                ]
+            }"#,
+        );
+
+        // Invalid features are skipped without dropping the ones following them
+        assert_eq!(db.get_nb_addresses(), 3);
+        assert_eq!(db.get_address(22, "Vesterbrogade").len(), 1);
+
+        let addr = db.get_address(14, "Vesterbrogade");
+        assert!((addr[0].lon - 12.1).abs() < 1e-9);
+        assert!((addr[0].lat - 55.1).abs() < 1e-9);
+        assert_eq!(addr[0].postcode, None);
+        assert_eq!(addr[0].meta.country.as_deref(), Some("DK"));
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_sequence() {
+        let db_file = "check_sequence.db";
+        let mut db = import(
+            db_file,
+            "\x1e{\"type\":\"Feature\",\"properties\":{\"husnr\":\"1\",\"vejnavn\":\"Strøget\"},\"geometry\":{\"type\":\"Point\",\"coordinates\":[12.57,55.67]}}\n\
+             \x1e{\"type\":\"Feature\",\"properties\":{\"husnr\":\"2\",\"vejnavn\":\"Strøget\"},\"geometry\":{\"type\":\"MultiPoint\",\"coordinates\":[[12.58,55.68]]}}\n\
+             {\"type\":\"Feature\",\"properties\":{\"husnr\":\"3\",\"vejnavn\":\"Strøget\"},\"geometry\":{\"type\":\"LineString\",\"coordinates\":[[12.5,55.6],[12.6,55.7]]}}\n\
+             {\"type\":\"Feature\",\"properties\":[\"4\",\"Strøget\"],\"geometry\":{\"type\":\"Point\",\"coordinates\":[12.57,55.67]}}\n\
+             {\"type\":\"Feature\",\"properties\":{\"husnr\":\"5\",\"vejnavn\":\"Strøget\"},\"geometry\":{\"type\":\"Point\",\"coordinates\":[12.59,55.69]}}\n",
+        );
+
+        assert_eq!(db.get_nb_addresses(), 3);
+        assert_eq!(db.get_address(5, "Strøget").len(), 1);
+
+        let addr = db.get_address(2, "Strøget");
+        assert_eq!((addr[0].lon, addr[0].lat), (12.58, 55.68));
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+}
@@ -0,0 +1,189 @@
+//! Decoding of the geometries of GeoPackage files, which are stored as a GeoPackage header followed
+//! by a WKB (Well-Known Binary) geometry.
+
+use std::fmt;
+
+/// Why a geometry couldn't be decoded into a point.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GeometryError {
+    /// The blob isn't a valid GeoPackage geometry.
+    Invalid,
+    /// The geometry is empty.
+    Empty,
+    /// The geometry isn't a point or a multipoint.
+    Unsupported,
+}
+
+impl fmt::Display for GeometryError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::Invalid => "invalid geometry",
+            Self::Empty => "empty geometry",
+            Self::Unsupported => "unsupported geometry",
+        })
+    }
+}
+
+/// WKB geometry types, without their dimensions.
+const WKB_POINT: u32 = 1;
+const WKB_MULTIPOINT: u32 = 4;
+
+/// A reader of binary data, in little or big endian.
+struct Reader<'a> {
+    data: &'a [u8],
+    little_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], GeometryError> {
+        if self.data.len() < len {
+            return Err(GeometryError::Invalid);
+        }
+
+        let (bytes, rest) = self.data.split_at(len);
+        self.data = rest;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8, GeometryError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, GeometryError> {
+        let bytes = self.bytes(4)?.try_into().expect("4 bytes");
+
+        Ok(if self.little_endian {
+            u32::from_le_bytes(bytes)
+        } else {
+            u32::from_be_bytes(bytes)
+        })
+    }
+
+    fn f64(&mut self) -> Result<f64, GeometryError> {
+        let bytes = self.bytes(8)?.try_into().expect("8 bytes");
+
+        Ok(if self.little_endian {
+            f64::from_le_bytes(bytes)
+        } else {
+            f64::from_be_bytes(bytes)
+        })
+    }
+}
+
+/// Decode a GeoPackage geometry into a point, in the coordinates of its layer: the point itself,
+/// or the first point of a multipoint. Returns `[x, y]`, which is `[lon, lat]` for geographic
+/// coordinates.
+pub(crate) fn decode_point(blob: &[u8]) -> Result<[f64; 2], GeometryError> {
+    // Header: magic, version, flags, srs_id and envelope
+    let mut header = Reader {
+        data: blob,
+        little_endian: false,
+    };
+
+    if header.bytes(2)? != b"GP" {
+        return Err(GeometryError::Invalid);
+    }
+
+    let _version = header.u8()?;
+    let flags = header.u8()?;
+    header.little_endian = flags & 1 == 1;
+    let _srs_id = header.u32()?;
+
+    if flags & 0b1_0000 != 0 {
+        return Err(GeometryError::Empty);
+    }
+
+    let envelope_len = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        _ => return Err(GeometryError::Invalid),
+    };
+
+    header.bytes(envelope_len)?;
+    let [x, y] = decode_wkb_point(header.data)?;
+
+    if x.is_nan() || y.is_nan() {
+        // Empty points are encoded with NaN coordinates
+        Err(GeometryError::Empty)
+    } else {
+        Ok([x, y])
+    }
+}
+
+/// Decode a WKB point or multipoint, see [`decode_point`].
+fn decode_wkb_point(wkb: &[u8]) -> Result<[f64; 2], GeometryError> {
+    let mut reader = Reader {
+        data: wkb,
+        little_endian: false,
+    };
+
+    reader.little_endian = match reader.u8()? {
+        0 => false,
+        1 => true,
+        _ => return Err(GeometryError::Invalid),
+    };
+
+    // ISO WKB adds 1000, 2000 or 3000 for dimensions, EWKB sets the highest bits.
+    let kind = (reader.u32()? & 0x0fff_ffff) % 1000;
+
+    match kind {
+        WKB_POINT => Ok([reader.f64()?, reader.f64()?]),
+        WKB_MULTIPOINT => {
+            if reader.u32()? == 0 {
+                return Err(GeometryError::Empty);
+            }
+
+            decode_wkb_point(reader.data)
+        }
+        _ => Err(GeometryError::Unsupported),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Encode a point as a little endian GeoPackage geometry without an envelope.
+    pub(crate) fn encode_point(x: f64, y: f64) -> Vec<u8> {
+        let mut blob = b"GP\x00\x01".to_vec();
+        blob.extend(4326u32.to_le_bytes());
+        blob.push(1);
+        blob.extend(WKB_POINT.to_le_bytes());
+        blob.extend(x.to_le_bytes());
+        blob.extend(y.to_le_bytes());
+        blob
+    }
+
+    #[test]
+    fn check_decode_point() {
+        assert_eq!(decode_point(&encode_point(2.35, 48.85)), Ok([2.35, 48.85]));
+
+        // Big endian multipoint Z with an envelope
+        let mut blob = b"GP\x00\x02".to_vec();
+        blob.extend(4326u32.to_be_bytes());
+        blob.extend([0; 32]);
+        blob.push(0);
+        blob.extend((1000 + WKB_MULTIPOINT).to_be_bytes());
+        blob.extend(1u32.to_be_bytes());
+        blob.push(0);
+        blob.extend((1000 + WKB_POINT).to_be_bytes());
+        blob.extend(7.44f64.to_be_bytes());
+        blob.extend(46.95f64.to_be_bytes());
+        blob.extend(540f64.to_be_bytes());
+        assert_eq!(decode_point(&blob), Ok([7.44, 46.95]));
+
+        // Empty flag
+        let mut empty = encode_point(0., 0.);
+        empty[3] |= 0b1_0000;
+        assert_eq!(decode_point(&empty), Err(GeometryError::Empty));
+
+        // Linestring
+        let mut line = encode_point(0., 0.);
+        line[9] = 2;
+        assert_eq!(decode_point(&line), Err(GeometryError::Unsupported));
+
+        assert_eq!(decode_point(b"GP\x00"), Err(GeometryError::Invalid));
+    }
+}
@@ -0,0 +1,506 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::path::Path;
+
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde::Deserialize;
+use tools::{
+    teprintln, tprintln, AddressMapping, Column, CompatibleDB, Crs, Record, Rejection, Rejections,
+};
+
+mod geometry;
+
+use crate::geometry::{decode_point, GeometryError};
+
+/// Identifier of the "undefined geographic coordinate reference system" of GeoPackage, whose
+/// coordinates are read as WGS84.
+const UNDEFINED_GEOGRAPHIC_SRS: i64 = 0;
+
+fn default_priority() -> f64 {
+    1.
+}
+
+/// Configuration of the importer, read from a mapping file, see [`GpkgConfig::from_file`].
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct GpkgConfig {
+    /// Feature tables to read, all of them if it is empty.
+    #[serde(default)]
+    pub tables: Vec<String>,
+    /// Base priority of the source in the deduplicator, 1 by default, like OpenAddresses.
+    #[serde(default = "default_priority")]
+    pub priority: f64,
+    /// Mapping of the columns of feature tables to the fields of addresses.
+    pub fields: AddressMapping,
+}
+
+impl GpkgConfig {
+    /// Read the configuration from a TOML file, such as:
+    ///
+    /// ```toml
+    /// tables = ["adresses"]
+    /// priority = 2.5
+    ///
+    /// [fields]
+    /// number = { columns = ["numero", "rep"], separator = "" }
+    /// street = "nom_voie"
+    /// postcode = "code_postal"
+    /// city = "nom_commune"
+    /// country = { value = "FR" }
+    /// ```
+    ///
+    /// Columns are given by their name. A field may be built from several columns, which are
+    /// joined with spaces, or with another separator (`{ columns = ["numero", "rep"], separator =
+    /// "" }`), or be a constant. The location of addresses is taken from the geometry of features,
+    /// unless `lat` and `lon` are mapped.
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let content = std::fs::read_to_string(path).map_err(|err| err.to_string())?;
+        Self::from_toml(&content)
+    }
+
+    /// Read the configuration from the content of a mapping file, see [`GpkgConfig::from_file`].
+    pub fn from_toml(content: &str) -> Result<Self, String> {
+        let config: Self = toml::from_str(content).map_err(|err| err.to_string())?;
+
+        if config.fields.lat.is_some() != config.fields.lon.is_some() {
+            return Err("fields `lat` and `lon` must be mapped together".to_string());
+        }
+
+        let by_index = config
+            .fields
+            .fields()
+            .flat_map(|field| field.columns())
+            .any(|column| matches!(column, Column::Index(_)));
+
+        if by_index {
+            return Err("columns can only be found by their name".to_string());
+        }
+
+        Ok(config)
+    }
+}
+
+/// A feature table of a GeoPackage, as listed in `gpkg_geometry_columns`.
+#[derive(Debug, PartialEq)]
+struct Layer {
+    table: String,
+    geometry_column: String,
+    srs_id: i64,
+    /// Organization defining the coordinate reference system, and its identifier in this
+    /// organization, such as `EPSG` and 4326.
+    srs: Option<(String, i64)>,
+}
+
+impl Layer {
+    /// Returns the coordinate reference system of the layer, if it is supported.
+    fn crs(&self) -> Option<Crs> {
+        match &self.srs {
+            Some((organization, id)) if organization.eq_ignore_ascii_case("EPSG") => {
+                Crs::from_epsg((*id).try_into().ok()?)
+            }
+            Some(_) => None,
+            None => (self.srs_id == UNDEFINED_GEOGRAPHIC_SRS).then_some(Crs::Wgs84),
+        }
+    }
+
+    /// Name of the coordinate reference system of the layer, for messages.
+    fn srs_name(&self) -> String {
+        match &self.srs {
+            Some((organization, id)) => format!("{}:{}", organization, id),
+            None => format!("srs_id {}", self.srs_id),
+        }
+    }
+}
+
+/// List the feature tables of a GeoPackage.
+fn list_layers(conn: &Connection) -> rusqlite::Result<Vec<Layer>> {
+    let mut stmt = conn.prepare(
+        "SELECT c.table_name, g.column_name, g.srs_id, s.organization, s.organization_coordsys_id
+        FROM gpkg_contents c
+        JOIN gpkg_geometry_columns g ON g.table_name = c.table_name
+        LEFT JOIN gpkg_spatial_ref_sys s ON s.srs_id = g.srs_id
+        WHERE c.data_type = 'features'
+        ORDER BY c.table_name",
+    )?;
+
+    let layers = stmt.query_map([], |row| {
+        let organization: Option<String> = row.get(3)?;
+        let id: Option<i64> = row.get(4)?;
+
+        Ok(Layer {
+            table: row.get(0)?,
+            geometry_column: row.get(1)?,
+            srs_id: row.get(2)?,
+            srs: organization.zip(id),
+        })
+    })?;
+
+    layers.collect()
+}
+
+/// Quote an identifier, such as the name of a table, to be used in a query.
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+/// A row of a feature table, whose columns are found by their name. Numbers are read as strings.
+struct Row<'a> {
+    header: &'a HashMap<String, usize>,
+    row: &'a rusqlite::Row<'a>,
+}
+
+impl<'a> Record for Row<'a> {
+    fn get(&self, column: &Column) -> Option<Cow<'_, str>> {
+        let Column::Name(name) = column else {
+            return None;
+        };
+
+        match self.row.get_ref(*self.header.get(name.as_str())?).ok()? {
+            ValueRef::Text(value) => Some(String::from_utf8_lossy(value)),
+            ValueRef::Integer(value) => Some(Cow::Owned(value.to_string())),
+            ValueRef::Real(value) => Some(Cow::Owned(value.to_string())),
+            ValueRef::Null | ValueRef::Blob(_) => None,
+        }
+    }
+}
+
+/// The entry point of the GeoPackage importer.
+///
+/// * The `file_path` argument is where the GeoPackage file is located.
+/// * The `config` argument tells which feature tables are read, and how their columns are mapped
+///   to the fields of addresses, see [`GpkgConfig::from_file`].
+/// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
+///   the data will be stored.
+///
+/// The location of addresses is taken from the geometry of features, which must be points or
+/// multipoints (the first point is used). Their coordinates are reprojected to WGS84 when the layer
+/// uses another coordinate reference system, and layers whose system isn't supported are skipped,
+/// see [`tools::Crs`].
+///
+/// Example:
+///
+/// ```no_run
+/// use std::path::Path;
+/// use tools::DB;
+/// use gpkg_addresses::{import_addresses, GpkgConfig};
+///
+/// let config = GpkgConfig::from_file(Path::new("mapping.toml")).expect("invalid mapping file");
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// import_addresses("somefile.gpkg", &config, &mut db);
+/// ```
+pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(
+    file_path: P,
+    config: &GpkgConfig,
+    db: &mut T,
+) {
+    let file_path = file_path.as_ref();
+    teprintln!("[GPKG] Reading `{}`", file_path.display());
+    let count_before = db.get_nb_addresses();
+
+    let conn = Connection::open_with_flags(file_path, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .expect("cannot open file");
+    let layers = list_layers(&conn).expect("cannot list feature tables");
+
+    for table in &config.tables {
+        if !layers.iter().any(|layer| &layer.table == table) {
+            teprintln!("[GPKG] Cannot find feature table `{}`", table);
+        }
+    }
+
+    for layer in layers {
+        if !config.tables.is_empty() && !config.tables.contains(&layer.table) {
+            continue;
+        }
+
+        let Some(crs) = layer.crs() else {
+            teprintln!(
+                "[GPKG] Skipping table `{}`: unsupported coordinate reference system {}",
+                layer.table,
+                layer.srs_name()
+            );
+            continue;
+        };
+
+        if let Err(err) = read_layer(&conn, &layer, crs, config, db) {
+            teprintln!("[GPKG] Cannot read table `{}`: {}", layer.table, err);
+        }
+    }
+
+    let count_after = db.get_nb_addresses();
+    tprintln!(
+        "[GPKG] Added {} addresses (total: {})",
+        count_after - count_before,
+        count_after
+    );
+}
+
+/// Read the addresses of a feature table, whose coordinates are in `crs`, into `db`.
+fn read_layer<T: CompatibleDB>(
+    conn: &Connection,
+    layer: &Layer,
+    crs: Crs,
+    config: &GpkgConfig,
+    db: &mut T,
+) -> rusqlite::Result<()> {
+    let mut stmt = conn.prepare(&format!("SELECT * FROM {}", quote(&layer.table)))?;
+
+    let header: HashMap<_, _> = stmt
+        .column_names()
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| (name.to_string(), index))
+        .collect();
+
+    let missing = config
+        .fields
+        .fields()
+        .flat_map(|field| field.columns())
+        .find(|column| matches!(column, Column::Name(name) if !header.contains_key(name.as_str())));
+
+    if let Some(column) = missing {
+        teprintln!(
+            "[GPKG] Skipping table `{}`: missing column {:?}",
+            layer.table,
+            column
+        );
+        return Ok(());
+    }
+
+    // The geometry is only read when the position isn't mapped to columns
+    let geometry_index = if config.fields.lat.is_some() {
+        None
+    } else if let Some(&index) = header.get(&layer.geometry_column) {
+        Some(index)
+    } else {
+        teprintln!(
+            "[GPKG] Skipping table `{}`: missing geometry column `{}`",
+            layer.table,
+            layer.geometry_column
+        );
+        return Ok(());
+    };
+
+    let mut rejections = Rejections::default();
+    let mut rows = stmt.query([])?;
+
+    while let Some(row) = rows.next()? {
+        let position = if let Some(geometry_index) = geometry_index {
+            let blob = match row.get_ref(geometry_index)? {
+                ValueRef::Blob(blob) => blob,
+                _ => {
+                    rejections.add(Rejection::MissingGeometry);
+                    continue;
+                }
+            };
+
+            match decode_point(blob).map(|[x, y]| crs.to_wgs84(x, y)) {
+                Ok(Ok(position)) => Some(position),
+                Ok(Err(_)) => {
+                    rejections.add(Rejection::InvalidCoordinates);
+                    continue;
+                }
+                Err(GeometryError::Empty) => {
+                    rejections.add(Rejection::MissingGeometry);
+                    continue;
+                }
+                Err(GeometryError::Invalid) => {
+                    rejections.add(Rejection::InvalidGeometry);
+                    continue;
+                }
+                Err(GeometryError::Unsupported) => {
+                    rejections.add(Rejection::UnsupportedGeometry);
+                    continue;
+                }
+            }
+        } else {
+            None
+        };
+
+        let record = Row {
+            header: &header,
+            row,
+        };
+
+        match config.fields.address(&record, position) {
+            Some(address) => db.insert(address),
+            None => rejections.add(Rejection::InvalidCoordinates),
+        }
+    }
+
+    rejections.log_skipped("GPKG", &layer.table);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::tests::encode_point;
+    use tools::DB;
+
+    const MAPPING: &str = r#"
+        [fields]
+        number = { columns = ["numero", "rep"], separator = "" }
+        street = "nom_voie"
+        postcode = "code_postal"
+        country = { value = "FR" }
+    "#;
+
+    /// Create a GeoPackage with a WGS84 layer, a Lambert-93 layer, a Web Mercator layer, a layer
+    /// missing its geometry column and an attribute table.
+    fn create_gpkg(path: &str) {
+        let _ = std::fs::remove_file(path); // we ignore any potential error
+        let conn = Connection::open(path).expect("failed to create GeoPackage");
+
+        conn.execute_batch(
+            "CREATE TABLE gpkg_spatial_ref_sys (
+                srs_name TEXT NOT NULL, srs_id INTEGER PRIMARY KEY, organization TEXT NOT NULL,
+                organization_coordsys_id INTEGER NOT NULL, definition TEXT NOT NULL
+            );
+            CREATE TABLE gpkg_contents (
+                table_name TEXT PRIMARY KEY, data_type TEXT NOT NULL, srs_id INTEGER
+            );
+            CREATE TABLE gpkg_geometry_columns (
+                table_name TEXT NOT NULL, column_name TEXT NOT NULL,
+                geometry_type_name TEXT NOT NULL, srs_id INTEGER NOT NULL
+            );
+            INSERT INTO gpkg_spatial_ref_sys VALUES ('WGS 84', 4326, 'EPSG', 4326, ''),
+                ('RGF93 / Lambert-93', 2154, 'EPSG', 2154, ''),
+                ('WGS 84 / Pseudo-Mercator', 3857, 'EPSG', 3857, '');
+            INSERT INTO gpkg_contents VALUES ('adresses', 'features', 4326),
+                ('adresses_l93', 'features', 2154), ('adresses_web', 'features', 3857),
+                ('adresses_sans_geom', 'features', 4326), ('communes', 'attributes', NULL);
+            INSERT INTO gpkg_geometry_columns VALUES ('adresses', 'geom', 'POINT', 4326),
+                ('adresses_l93', 'geom', 'POINT', 2154), ('adresses_web', 'geom', 'POINT', 3857),
+                ('adresses_sans_geom', 'geom', 'POINT', 4326);
+            CREATE TABLE adresses (
+                fid INTEGER PRIMARY KEY, geom BLOB, numero INTEGER, rep TEXT, nom_voie TEXT,
+                code_postal TEXT
+            );
+            CREATE TABLE adresses_l93 (
+                fid INTEGER PRIMARY KEY, geom BLOB, numero INTEGER, rep TEXT, nom_voie TEXT,
+                code_postal TEXT
+            );
+            CREATE TABLE adresses_web (fid INTEGER PRIMARY KEY, geom BLOB, numero INTEGER);
+            CREATE TABLE adresses_sans_geom (
+                fid INTEGER PRIMARY KEY, numero INTEGER, rep TEXT, nom_voie TEXT, code_postal TEXT
+            );
+            INSERT INTO adresses_sans_geom (numero, nom_voie) VALUES (9, 'Rue Buffon');
+            CREATE TABLE communes (insee TEXT);",
+        )
+        .unwrap();
+
+        let mut insert = conn
+            .prepare("INSERT INTO adresses (geom, numero, rep, nom_voie, code_postal) VALUES (?1, ?2, ?3, ?4, ?5)")
+            .unwrap();
+        insert
+            .execute(rusqlite::params![
+                encode_point(2.3585, 48.8427),
+                12,
+                "bis",
+                "Rue Buffon",
+                "75005"
+            ])
+            .unwrap();
+        insert
+            .execute(rusqlite::params![
+                encode_point(2.3586, 48.8428),
+                14,
+                None::<String>,
+                "Rue Buffon",
+                "75005"
+            ])
+            .unwrap();
+        insert
+            .execute(rusqlite::params![
+                None::<Vec<u8>>,
+                16,
+                None::<String>,
+                "Rue Buffon",
+                "75005"
+            ])
+            .unwrap();
+        insert
+            .execute(rusqlite::params![
+                encode_point(200., 48.8428),
+                18,
+                None::<String>,
+                "Rue Buffon",
+                "75005"
+            ])
+            .unwrap();
+
+        let mut insert = conn
+            .prepare("INSERT INTO adresses_l93 (geom, numero, nom_voie) VALUES (?1, ?2, ?3)")
+            .unwrap();
+        insert
+            .execute(rusqlite::params![
+                encode_point(648_237.16, 6_862_256.11),
+                5,
+                "Avenue Gustave Eiffel"
+            ])
+            .unwrap();
+        // In the Netherlands
+        insert
+            .execute(rusqlite::params![
+                encode_point(1_000_000., 7_300_000.),
+                7,
+                "Avenue Gustave Eiffel"
+            ])
+            .unwrap();
+    }
+
+    #[test]
+    fn check_config() {
+        let config = GpkgConfig::from_toml(MAPPING).unwrap();
+        assert!(config.tables.is_empty());
+        assert_eq!(config.priority, 1.);
+        let config = GpkgConfig::from_toml(&format!("priority = 2.5\n{}", MAPPING)).unwrap();
+        assert_eq!(config.priority, 2.5);
+        assert!(GpkgConfig::from_toml("[fields]\nlon = \"x\"").is_err());
+        assert!(GpkgConfig::from_toml("[fields]\nstreet = 2").is_err());
+        assert!(GpkgConfig::from_toml("table = \"a\"\n[fields]").is_err());
+    }
+
+    #[test]
+    fn check_import() {
+        let gpkg_file = "check_import.gpkg";
+        create_gpkg(gpkg_file);
+
+        let conn = Connection::open(gpkg_file).unwrap();
+        let layers = list_layers(&conn).unwrap();
+        assert_eq!(
+            layers
+                .iter()
+                .map(|layer| (layer.table.as_str(), layer.crs()))
+                .collect::<Vec<_>>(),
+            [
+                ("adresses", Some(Crs::Wgs84)),
+                ("adresses_l93", Some(Crs::Lambert93)),
+                ("adresses_sans_geom", Some(Crs::Wgs84)),
+                ("adresses_web", None)
+            ]
+        );
+        assert_eq!(layers[3].srs_name(), "EPSG:3857");
+
+        let config = GpkgConfig::from_toml(MAPPING).unwrap();
+        let db_file = "check_gpkg_import.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        import_addresses(gpkg_file, &config, &mut db);
+        assert_eq!(db.get_nb_addresses(), 3);
+
+        let addr = db.get_address(14, "Rue Buffon");
+        assert_eq!((addr[0].lon, addr[0].lat), (2.3586, 48.8428));
+        assert_eq!(addr[0].postcode.as_deref(), Some("75005"));
+        assert_eq!(addr[0].meta.country.as_deref(), Some("FR"));
+        assert_eq!(db.get_address(12, "Rue Buffon").len(), 0); // the number is "12bis"
+        assert_eq!(db.get_address(9, "Rue Buffon").len(), 0); // the table has no geometry
+
+        let addr = db.get_address(5, "Avenue Gustave Eiffel");
+        assert!((addr[0].lon - 2.2945).abs() < 1e-4 && (addr[0].lat - 48.85826).abs() < 1e-4);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        let _ = std::fs::remove_file(gpkg_file); // we ignore any potential error
+    }
+}
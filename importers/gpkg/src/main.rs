@@ -0,0 +1,32 @@
+use std::env;
+use std::path::Path;
+
+use gpkg_addresses::GpkgConfig;
+use tools::{teprintln, tprintln, CompatibleDB, DB};
+
+fn main() {
+    let args = env::args().collect::<Vec<String>>();
+    if args.len() < 3 {
+        teprintln!("Expected mapping file and GeoPackage file");
+        return;
+    }
+
+    let config = GpkgConfig::from_file(Path::new(&args[1]))
+        .unwrap_or_else(|err| panic!("invalid mapping file {:?}: {}", args[1], err));
+
+    let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+    gpkg_addresses::import_addresses(&args[2], &config, &mut db);
+
+    tprintln!(
+        "Got {} addresses in {} cities (and {} errors)",
+        db.get_nb_addresses(),
+        db.get_nb_cities(),
+        db.get_nb_errors(),
+    );
+
+    teprintln!("Errors by categories:");
+    let rows = db.get_nb_by_errors_kind();
+    for (kind, nb) in rows {
+        teprintln!("  {} => {} occurences", kind, nb);
+    }
+}
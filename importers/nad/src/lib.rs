@@ -0,0 +1,342 @@
+use std::io::BufRead;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv::{ReaderBuilder, StringRecord};
+use tools::{
+    for_each_entry, strip_compression_extension, teprintln, tprintln, valid_coordinates, Address,
+    AddressMeta, CompatibleDB,
+};
+
+/// Value used by the NAD for fields which were not filled by the provider of the address.
+const NOT_STATED: &str = "Not stated";
+
+/// Extensions of the files read from zip archives.
+const EXTENSIONS: &[&str] = &["csv", "txt"];
+
+/// Columns of the NAD text files which are used by the importer.
+struct Columns {
+    number_prefix: Option<usize>,
+    number: usize,
+    number_suffix: Option<usize>,
+    /// Parts of the street name, in the order they are written.
+    street: [Option<usize>; 8],
+    street_full: Option<usize>,
+    /// Parts of the subaddress, in the order they are written.
+    unit: [Option<usize>; 4],
+    subaddress: Option<usize>,
+    county: Option<usize>,
+    municipality: Option<usize>,
+    post_city: Option<usize>,
+    state: Option<usize>,
+    zip_code: Option<usize>,
+    uuid: Option<usize>,
+    nad_source: Option<usize>,
+    lon: usize,
+    lat: usize,
+}
+
+impl Columns {
+    /// Find the columns in the header of the file, ignoring their case as it changed between
+    /// releases. Only the house number and the coordinates are required.
+    fn from_headers(headers: &StringRecord) -> Result<Self, String> {
+        let find = |name: &str| {
+            headers
+                .iter()
+                .position(|header| header.trim().eq_ignore_ascii_case(name))
+        };
+
+        let require = |names: &[&str]| {
+            names
+                .iter()
+                .find_map(|name| find(name))
+                .ok_or_else(|| format!("missing column `{}`", names[0]))
+        };
+
+        Ok(Self {
+            number_prefix: find("AddNum_Pre"),
+            number: require(&["Add_Number"])?,
+            number_suffix: find("AddNum_Suf"),
+            street: [
+                find("St_PreMod"),
+                find("St_PreDir"),
+                find("St_PreTyp"),
+                find("St_PreSep"),
+                find("St_Name"),
+                find("St_PosTyp"),
+                find("St_PosDir"),
+                find("St_PosMod"),
+            ],
+            street_full: find("StNam_Full"),
+            unit: [find("Building"), find("Floor"), find("Unit"), find("Room")],
+            subaddress: find("SubAddress"),
+            county: find("County"),
+            municipality: find("Inc_Muni"),
+            post_city: find("Post_City"),
+            state: find("State"),
+            zip_code: find("Zip_Code"),
+            uuid: find("UUID"),
+            nad_source: find("NAD_Source"),
+            lon: require(&["Longitude", "Long"])?,
+            lat: require(&["Latitude", "Lat"])?,
+        })
+    }
+}
+
+/// Helper macro to get a non-empty CSV field, if its column exists. Fields which were "Not stated"
+/// are read as empty.
+macro_rules! get {
+    ($index:expr, $records:expr) => {
+        $index
+            .and_then(|index| $records.get(index))
+            .map(str::trim)
+            .filter(|x| !x.is_empty() && !x.eq_ignore_ascii_case(NOT_STATED))
+    };
+}
+
+/// Helper macro to convert a CSV field into an `f64`.
+macro_rules! get_f64 {
+    ($index:expr, $records:expr) => {
+        match get!(Some($index), $records).and_then(|x| f64::from_str(x).ok()) {
+            Some(x) => x,
+            None => continue,
+        }
+    };
+}
+
+/// Join the non-empty fields of several columns with spaces, returns `None` if they are all empty.
+fn join(columns: &[Option<usize>], record: &StringRecord) -> Option<String> {
+    let parts: Vec<_> = columns
+        .iter()
+        .filter_map(|&column| get!(column, record))
+        .collect();
+
+    (!parts.is_empty()).then(|| parts.join(" "))
+}
+
+/// Build the house number from its prefix, number and suffix, such as `N 123`, `123 1/2` or
+/// `123 A`. Parts are joined with spaces, unless they are joined with a hyphen, as in `37-12` for
+/// addresses of Queens.
+fn house_number(prefix: Option<&str>, number: &str, suffix: Option<&str>) -> String {
+    let mut res = String::new();
+
+    for part in [prefix, Some(number), suffix].into_iter().flatten() {
+        if !res.is_empty() && !res.ends_with('-') && !part.starts_with('-') {
+            res.push(' ');
+        }
+
+        res.push_str(part);
+    }
+
+    res
+}
+
+/// Read a ZIP code, which may have lost its leading zeros when the file went through a
+/// spreadsheet. Returns `None` for codes which are not 5 digits long once padded, or are only
+/// zeros.
+fn zip_code(raw: &str) -> Option<String> {
+    if raw.is_empty() || raw.len() > 5 || !raw.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+
+    let zip_code = format!("{:0>5}", raw);
+    (zip_code != "00000").then_some(zip_code)
+}
+
+/// The entry point of the NAD (**National Address Database**) importer.
+///
+/// * The `file_path` argument is where the NAD text file is located. Compressed files and zip
+///   archives, such as the `NAD_r<release>_TXT.zip` exports, are decompressed on the fly, see
+///   [`tools::for_each_entry`].
+/// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
+///   the data will be stored.
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::DB;
+/// use nad::import_addresses;
+///
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// import_addresses("NAD_r17_TXT.zip", &mut db);
+/// ```
+pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(file_path: P, db: &mut T) {
+    let file_path = file_path.as_ref();
+    teprintln!("[NAD] Reading `{}`", file_path.display());
+    let count_before = db.get_nb_addresses();
+
+    for_each_entry(file_path, |name, input| {
+        // Archives hold the schema and the documentation of the export as well
+        let stripped = strip_compression_extension(name);
+        let extension = stripped
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+
+        if name == file_path || EXTENSIONS.contains(&extension.to_ascii_lowercase().as_str()) {
+            read_csv(name, input, db)
+        }
+    })
+    .expect("cannot read file");
+
+    let count_after = db.get_nb_addresses();
+    tprintln!(
+        "[NAD] Added {} addresses (total: {})",
+        count_after - count_before,
+        count_after
+    );
+}
+
+/// Read the addresses of a NAD text file into `db`.
+fn read_csv<T: CompatibleDB>(file_path: &Path, input: &mut dyn BufRead, db: &mut T) {
+    let mut rdr = ReaderBuilder::new().flexible(true).from_reader(input);
+
+    let columns = match rdr
+        .headers()
+        .map_err(|e| e.to_string())
+        .and_then(Columns::from_headers)
+    {
+        Ok(columns) => columns,
+        Err(e) => {
+            teprintln!("[NAD] Invalid header in `{}`: {}", file_path.display(), e);
+            return;
+        }
+    };
+
+    let mut count_no_number = 0;
+    let mut count_invalid_coordinates = 0;
+
+    for x in rdr.into_records() {
+        let x = match x {
+            Ok(x) => x,
+            Err(e) => {
+                teprintln!("[NAD] Invalid record found: {}", e);
+                continue;
+            }
+        };
+
+        let Some(number) = get!(Some(columns.number), x) else {
+            count_no_number += 1;
+            continue;
+        };
+
+        let (lon, lat) = (get_f64!(columns.lon, x), get_f64!(columns.lat, x));
+
+        if !valid_coordinates(lon, lat) || (lon == 0. && lat == 0.) {
+            count_invalid_coordinates += 1;
+            continue;
+        }
+
+        let number = house_number(
+            get!(columns.number_prefix, x),
+            number,
+            get!(columns.number_suffix, x),
+        );
+
+        let street = match get!(columns.street[4], x) {
+            Some(_) => join(&columns.street, &x),
+            None => get!(columns.street_full, x).map(str::to_string),
+        };
+
+        let unit = join(&columns.unit, &x).or_else(|| get!(columns.subaddress, x).map(Into::into));
+
+        db.insert(Address {
+            lat,
+            lon,
+            number: Some(number.into()),
+            street: street.map(|x| x.into()),
+            unit: unit.map(|x| x.into()),
+            city: get!(columns.post_city, x)
+                .or_else(|| get!(columns.municipality, x))
+                .map(|x| x.into()),
+            district: get!(columns.county, x).map(|x| x.into()),
+            region: get!(columns.state, x).map(|x| x.into()),
+            postcode: get!(columns.zip_code, x)
+                .and_then(zip_code)
+                .map(|x| x.into()),
+            source_id: get!(columns.uuid, x)
+                .map(|uuid| uuid.trim_start_matches('{').trim_end_matches('}').into()),
+            meta: AddressMeta {
+                country: Some("US".into()),
+                origin: get!(columns.nad_source, x).map(|x| x.into()),
+                ..AddressMeta::default()
+            },
+        });
+    }
+
+    if count_no_number > 0 {
+        tprintln!(
+            "[NAD] Ignored {} addresses without a house number",
+            count_no_number
+        );
+    }
+
+    if count_invalid_coordinates > 0 {
+        tprintln!(
+            "[NAD] Ignored {} addresses with invalid coordinates",
+            count_invalid_coordinates
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tools::DB;
+
+    const INPUT: &str = "\
+OID_,AddNum_Pre,Add_Number,AddNum_Suf,AddNo_Full,St_PreMod,St_PreDir,St_PreTyp,St_PreSep,St_Name,St_PosTyp,St_PosDir,St_PosMod,StNam_Full,Building,Floor,Unit,Room,SubAddress,County,Inc_Muni,Post_City,State,Zip_Code,UUID,Longitude,Latitude,NAD_Source
+1,,1600,,1600,,,,,Pennsylvania,Avenue,NW,,Pennsylvania Avenue NW,,,,,,District of Columbia,Washington,Washington,DC,20500,{2A7C1E5B-0001},-77.0365,38.8977,DC
+2,,221,1/2,221 1/2,,North,,,Main,Street,,,North Main Street,,,Apt 4,,,Middlesex,Cambridge,Not stated,MA,2139,{2A7C1E5B-0002},-71.1050,42.3650,MA
+3,37-,12,,37-12,,,,,,,,,80th Street,Bldg B,,,,,Queens,New York,Jackson Heights,NY,11372,{2A7C1E5B-0003},-73.8860,40.7500,NY
+4,,,,,,,,,Main,Street,,,Main Street,,,,,,Middlesex,Cambridge,Cambridge,MA,02139,{2A7C1E5B-0004},-71.1050,42.3650,MA
+5,,10,,10,,,,,Main,Street,,,Main Street,,,,,,Middlesex,Cambridge,Cambridge,MA,02139,{2A7C1E5B-0005},0,0,MA
+";
+
+    #[test]
+    fn check_helpers() {
+        assert_eq!(house_number(Some("N"), "123", None), "N 123");
+        assert_eq!(house_number(None, "123", Some("1/2")), "123 1/2");
+        assert_eq!(house_number(Some("37-"), "12", None), "37-12");
+        assert_eq!(zip_code("2139").as_deref(), Some("02139"));
+        assert_eq!(zip_code("20500").as_deref(), Some("20500"));
+        assert_eq!(zip_code("0"), None);
+        assert_eq!(zip_code("20500-0003"), None);
+    }
+
+    #[test]
+    fn check_import() {
+        let csv_file = std::env::temp_dir().join("check_nad_import.txt");
+        let db_file = "check_nad_import.db";
+        std::fs::write(&csv_file, INPUT).expect("failed to write input");
+
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        import_addresses(&csv_file, &mut db);
+        assert_eq!(db.get_nb_addresses(), 3);
+
+        let address = db.remove_by_source_id("2A7C1E5B-0001").pop();
+        let address = address.expect("missing address");
+        assert_eq!(address.number.as_deref(), Some("1600"));
+        assert_eq!(address.street.as_deref(), Some("Pennsylvania Avenue NW"));
+        assert_eq!(address.region.as_deref(), Some("DC"));
+        assert_eq!(address.meta.country.as_deref(), Some("US"));
+
+        let address = db.remove_by_source_id("2A7C1E5B-0002").pop();
+        let address = address.expect("missing address");
+        assert_eq!(address.number.as_deref(), Some("221 1/2"));
+        assert_eq!(address.street.as_deref(), Some("North Main Street"));
+        assert_eq!(address.unit.as_deref(), Some("Apt 4"));
+        assert_eq!(address.city.as_deref(), Some("Cambridge"));
+        assert_eq!(address.postcode.as_deref(), Some("02139"));
+
+        let address = db.remove_by_source_id("2A7C1E5B-0003").pop();
+        let address = address.expect("missing address");
+        assert_eq!(address.number.as_deref(), Some("37-12"));
+        assert_eq!(address.street.as_deref(), Some("80th Street"));
+        assert_eq!(address.unit.as_deref(), Some("Bldg B"));
+        assert_eq!(address.city.as_deref(), Some("Jackson Heights"));
+
+        let _ = std::fs::remove_file(csv_file); // we ignore any potential error
+        let _ = std::fs::remove_file(db_file);
+    }
+}
@@ -1,57 +1,97 @@
-use std::ffi::OsStr;
-use std::fs::{self, File};
-use std::io::BufReader;
-use std::path::Path;
+mod location;
 
-use serde::Deserialize;
-use smartstring::alias::String;
-use tools::{teprint, teprintln, tprintln, Address, CompatibleDB};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::BufRead;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
 
-/// Size of the read buffer put on top of the input CSV file
-const GJ_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+use crate::location::SourceLocation;
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use smartstring::alias::String;
+use tools::{
+    for_each_entry, representative_point, strip_compression_extension, teprintln, tprintln,
+    valid_coordinates, Address, AddressSink, CompatibleDB, LockedDB, OpenAddressLegacy, Rejection,
+    Rejections,
+};
 
-/// Subset of a Geojson Feature, expected to contain a point.
+/// Subset of a Geojson Feature. Its geometry may be missing or `null`, in which case the feature is
+/// rejected.
 #[derive(Deserialize)]
 pub struct OpenAddressFeature {
     // pub type: String,
+    #[serde(default)]
     properties: OpenAddressProperties,
-    geometry: OpenAddressGeometry,
+    #[serde(default)]
+    geometry: Option<OpenAddressGeometry>,
 }
 
-/// Subset of a geojson geometry, expected to be a point
+/// Subset of a geojson geometry. Only points, multipoints, polygons and multipolygons are
+/// supported, see [`OpenAddressGeometry::representative_point`].
 #[derive(Deserialize)]
 pub struct OpenAddressGeometry {
-    // pub type: String,
-    pub coordinates: [f64; 2],
+    #[serde(rename = "type")]
+    pub kind: String,
+    #[serde(default)]
+    pub coordinates: Value,
 }
 
-#[derive(Deserialize)]
+/// Properties of a feature, any of which may be missing or `null`. Numbers are also accepted, as
+/// some sources give house numbers or postcodes as such.
+#[derive(Default, Deserialize)]
 pub struct OpenAddressProperties {
     // pub hash: String,
     // pub id: String,
-    pub number: String,
-    pub street: String,
-    pub unit: String,
-    pub city: String,
-    pub district: String,
-    pub region: String,
-    pub postcode: String,
-}
-
-impl From<OpenAddressFeature> for Address {
-    fn from(val: OpenAddressFeature) -> Self {
-        let filter_empty = |field: String| {
-            if field.is_empty() {
-                None
-            } else {
-                Some(field)
-            }
-        };
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub number: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub street: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub unit: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub city: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub district: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub region: Option<String>,
+    #[serde(default, deserialize_with = "lenient_string")]
+    pub postcode: Option<String>,
+}
+
+/// Deserialize a property which may be a string, a number, or anything else which is ignored.
+fn lenient_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::String(value) => Some(value.trim().into()),
+        Value::Number(value) => Some(value.to_string().into()),
+        _ => None,
+    })
+}
+
+impl OpenAddressGeometry {
+    /// The point used as the location of an address, see [`tools::representative_point`].
+    /// Returns `[lon, lat]`.
+    pub fn representative_point(&self) -> Result<[f64; 2], Rejection> {
+        representative_point(&self.kind, &self.coordinates).map_err(Into::into)
+    }
+}
+
+impl TryFrom<OpenAddressFeature> for Address {
+    type Error = Rejection;
+
+    fn try_from(val: OpenAddressFeature) -> Result<Self, Rejection> {
+        let filter_empty = |field: Option<String>| field.filter(|field| !field.is_empty());
+
+        let [lon, lat] = val
+            .geometry
+            .ok_or(Rejection::MissingGeometry)?
+            .representative_point()?;
 
-        let [lon, lat] = val.geometry.coordinates;
         let props = val.properties;
 
-        Address {
+        Ok(Address {
             lat,
             lon,
             number: filter_empty(props.number),
@@ -61,41 +101,322 @@ impl From<OpenAddressFeature> for Address {
             district: filter_empty(props.district),
             region: filter_empty(props.region),
             postcode: filter_empty(props.postcode),
+            source_id: None,
+            meta: Default::default(),
+        })
+    }
+}
+
+/// Check if a file holds **OpenAddresses** features from its name, which may be compressed.
+fn is_geojson(path: &Path) -> bool {
+    strip_compression_extension(path).extension() == Some("geojson".as_ref())
+}
+
+/// Check if a file holds **OpenAddresses** rows in the legacy CSV format from its name, which may
+/// be compressed.
+fn is_csv(path: &Path) -> bool {
+    strip_compression_extension(path).extension() == Some("csv".as_ref())
+}
+
+/// Number of addresses sent at once to the sink by the threads reading files.
+const BATCH_SIZE: usize = 1000;
+
+/// Addresses read from a file, which are sent to the sink by batches, with the features that were
+/// rejected.
+struct FileOutput<'s, S: AddressSink> {
+    sink: &'s S,
+    location: SourceLocation,
+    batch: Vec<Address>,
+    count: usize,
+    rejections: Rejections,
+}
+
+impl<'s, S: AddressSink> FileOutput<'s, S> {
+    fn new(sink: &'s S) -> Self {
+        Self {
+            sink,
+            location: SourceLocation::default(),
+            batch: Vec::with_capacity(BATCH_SIZE),
+            count: 0,
+            rejections: Rejections::default(),
         }
     }
+
+    fn insert(&mut self, mut address: Address) {
+        self.location.apply(&mut address);
+        self.batch.push(address);
+        self.count += 1;
+
+        if self.batch.len() >= BATCH_SIZE {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        let batch = std::mem::replace(&mut self.batch, Vec::with_capacity(BATCH_SIZE));
+        self.sink.insert_batch(batch);
+    }
 }
 
-/// This function is called on every CSV file encountered in the given folder tree in the
-/// `import_addresses` function. It simply reads it and fills the `db` object.
-fn read_csv<P: AsRef<Path>, T: CompatibleDB>(db: &mut T, file_path: P) {
-    let file = BufReader::with_capacity(
-        GJ_BUFFER_SIZE,
-        File::open(&file_path).expect("cannot open file"),
-    );
+/// Read the rows of a CSV file in the legacy **OpenAddresses** format (see [`OpenAddressLegacy`])
+/// into `out`. This is also the format of the dumps of the deduplicator, which can then be imported
+/// again.
+fn read_csv<S: AddressSink>(file_path: &Path, input: &mut dyn BufRead, out: &mut FileOutput<S>) {
+    let mut reader = csv::Reader::from_reader(input);
+
+    for row in reader.deserialize::<OpenAddressLegacy>() {
+        match row {
+            Ok(row) if valid_coordinates(row.lon, row.lat) => out.insert(row.into()),
+            Ok(_) => out.rejections.add(Rejection::InvalidCoordinates),
+            Err(err) => {
+                teprintln!("[OA] Invalid record found in {:?}: {}", file_path, err);
+                out.rejections.add(Rejection::InvalidRecord);
+            }
+        }
+    }
+}
 
-    let rdr = serde_json::Deserializer::from_reader(file);
+/// This function is called on every GeoJSON file encountered in the given folder tree in the
+/// `import_addresses` function. It simply reads it into `out`. The file may hold a sequence of
+/// features, such as newline-delimited GeoJSON, or feature collections, which may span several
+/// lines. Values are read one after the other, so that an invalid feature doesn't prevent reading
+/// the following ones, but nothing can be read after invalid JSON.
+fn read_geojson<S: AddressSink>(
+    file_path: &Path,
+    input: &mut dyn BufRead,
+    out: &mut FileOutput<S>,
+) {
+    for value in serde_json::Deserializer::from_reader(input).into_iter::<Value>() {
+        match value {
+            Ok(Value::Object(mut collection))
+                if collection.get("type").and_then(Value::as_str) == Some("FeatureCollection") =>
+            {
+                let features = match collection.remove("features") {
+                    Some(Value::Array(features)) => features,
+                    _ => Vec::new(),
+                };
 
-    for address in rdr.into_iter::<OpenAddressFeature>() {
-        match address {
-            Ok(address) => db.insert(address.into()),
-            Err(err) => teprintln!(
-                "[OA] Invalid record found in {:?}: {}",
-                file_path.as_ref(),
-                err
-            ),
+                for feature in features {
+                    read_feature(file_path, feature, out);
+                }
+            }
+            Ok(feature) => read_feature(file_path, feature, out),
+            Err(err) => {
+                teprintln!("[OA] Invalid content found in {:?}: {}", file_path, err);
+                out.rejections.add(Rejection::InvalidRecord);
+                return;
+            }
         }
     }
 }
 
+/// Read a feature of a GeoJSON file into `out`, or count why it was rejected.
+fn read_feature<S: AddressSink>(file_path: &Path, feature: Value, out: &mut FileOutput<S>) {
+    let address = OpenAddressFeature::deserialize(feature)
+        .map_err(|err| {
+            teprintln!("[OA] Invalid record found in {:?}: {}", file_path, err);
+            Rejection::InvalidRecord
+        })
+        .and_then(Address::try_from);
+
+    match address {
+        Ok(address) => out.insert(address),
+        Err(reason) => out.rejections.add(reason),
+    }
+}
+
+/// List the files to import under `base_path`, sorted so that they are always reported in the
+/// same order.
+fn list_files(base_path: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut todo = vec![base_path.to_path_buf()];
+
+    while let Some(path) = todo.pop() {
+        if path.is_dir() {
+            fs::read_dir(path)
+                .expect("folder not found")
+                .filter_map(|item| {
+                    item.map_err(|err| teprintln!("Failed to read path: {}", err))
+                        .ok()
+                })
+                .for_each(|item| todo.push(item.path()));
+        } else if is_geojson(&path) || is_csv(&path) || path.extension() == Some("zip".as_ref()) {
+            files.push(path);
+        }
+    }
+
+    files.sort();
+    files
+}
+
+/// Number of batches a thread may read ahead of the sink, for a file whose turn to be inserted
+/// hasn't come yet.
+const PENDING_BATCHES: usize = 16;
+
+/// Progress of the reading of a file, sent by the thread reading it.
+enum FileEvent {
+    /// Addresses read from the file.
+    Batch(Vec<Address>),
+    /// The file was read, with this number of addresses and these rejections.
+    Done(usize, Rejections),
+}
+
+/// Sends the addresses of a file to the thread which inserts them into the actual sink, so that
+/// files are inserted in order. Sending blocks while the file has too many pending batches.
+struct FileSink(mpsc::SyncSender<FileEvent>);
+
+impl AddressSink for FileSink {
+    fn insert(&self, addr: Address) {
+        self.insert_batch(vec![addr])
+    }
+
+    fn insert_batch(&self, addrs: Vec<Address>) {
+        // The receiver only hangs up if the importer is interrupted
+        let _ = self.0.send(FileEvent::Batch(addrs));
+    }
+}
+
+/// Read a file, or each entry of a zip archive, into `sink`. Returns the number of addresses read
+/// and the features that were rejected.
+fn read_file<S: AddressSink>(path: &Path, short_name: &Path, sink: &S) -> (usize, Rejections) {
+    let mut out = FileOutput::new(sink);
+
+    let result = for_each_entry(path, |name, input| {
+        // Entries of archives are laid out like the tree itself
+        out.location = SourceLocation::from_path(if name == path { short_name } else { name });
+
+        if is_geojson(name) {
+            read_geojson(name, input, &mut out)
+        } else if is_csv(name) {
+            read_csv(name, input, &mut out)
+        }
+    });
+
+    if let Err(err) = result {
+        teprintln!("[OA] Failed to read {:?}: {}", path, err);
+    }
+
+    out.flush();
+    (out.count, out.rejections)
+}
+
+/// Read the **OpenAddresses** files under `base_path` with `nb_threads` threads, and insert their
+/// addresses into `sink`. Returns the number of addresses read.
+///
+/// Files are spread over the threads, but their addresses are inserted by the calling thread in the
+/// order of their paths, whatever the order in which they are read, so that the result doesn't
+/// depend on the number of threads when files hold conflicting addresses. A thread reading a file
+/// whose turn hasn't come yet only reads a few batches ahead. A line is printed for each file in the
+/// same order.
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::{LockedDB, DB};
+/// use openaddresses::import_addresses_parallel;
+///
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// import_addresses_parallel("some_folder", &LockedDB::new(&mut db), 4);
+/// ```
+pub fn import_addresses_parallel<P: AsRef<Path>, S: AddressSink>(
+    base_path: P,
+    sink: &S,
+    nb_threads: usize,
+) -> usize {
+    let base_path = base_path.as_ref();
+    let files = list_files(base_path);
+    let next_file = AtomicUsize::new(0);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::scope(|scope| {
+        for _ in 0..nb_threads.clamp(1, files.len().max(1)) {
+            let sender = sender.clone();
+            let (files, next_file) = (&files, &next_file);
+
+            scope.spawn(move || loop {
+                let index = next_file.fetch_add(1, Ordering::Relaxed);
+
+                let Some(path) = files.get(index) else {
+                    break;
+                };
+
+                let (events_sender, events) = mpsc::sync_channel(PENDING_BATCHES);
+
+                if sender.send((index, events)).is_err() {
+                    break;
+                }
+
+                let short_name = path.strip_prefix(base_path).unwrap_or(path);
+                let file_sink = FileSink(events_sender);
+                let (file_count, rejections) = read_file(path, short_name, &file_sink);
+                let _ = file_sink.0.send(FileEvent::Done(file_count, rejections));
+            });
+        }
+
+        drop(sender);
+
+        // Files are inserted in order, the ones started early wait for the previous ones.
+        let mut started = BTreeMap::new();
+        let mut next_insert = 0;
+        let mut count = 0;
+
+        for (index, events) in receiver {
+            started.insert(index, events);
+
+            while let Some(events) = started.remove(&next_insert) {
+                let path = &files[next_insert];
+                let short_name = path.strip_prefix(base_path).unwrap_or(path);
+                next_insert += 1;
+
+                let Some((file_count, rejections)) =
+                    events.into_iter().find_map(|event| match event {
+                        FileEvent::Batch(batch) => {
+                            sink.insert_batch(batch);
+                            None
+                        }
+                        FileEvent::Done(file_count, rejections) => Some((file_count, rejections)),
+                    })
+                else {
+                    teprintln!("[OA] Failed to read {:?}", short_name);
+                    continue;
+                };
+
+                count += file_count;
+
+                if rejections.is_empty() {
+                    teprintln!(
+                        "[OA] Read {:<40} ... {} addresses",
+                        short_name.display(),
+                        file_count,
+                    );
+                } else {
+                    teprintln!(
+                        "[OA] Read {:<40} ... {} addresses, rejected: {}",
+                        short_name.display(),
+                        file_count,
+                        rejections
+                    );
+                }
+            }
+        }
+
+        count
+    })
+}
+
 /// The entry point of the **OpenAddresses** importer.
 ///
-/// * The `base_path` argument is where the top folder containing the CSV files is located.
+/// * The `base_path` argument is where the top folder containing the GeoJSON files, or the CSV files
+///   of the legacy format, is located. Files may be compressed, and zip archives, such as the
+///   batches of **OpenAddresses**, are read without being extracted, see [`tools::for_each_entry`].
 /// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
 ///   the data will be stored.
 ///
-/// Considering it's calling the `read_csv` function on every CSV files it finds, it could be pretty
-/// simply run in parallel. It'd require `db` to be able to handle multi-threading though. To be
-/// done later I guess?
+/// The country and region of addresses are inferred from the path of their file in the tree, see
+/// `SourceLocation`.
+///
+/// Files are read in parallel by as many threads as there are CPUs, and their addresses are inserted
+/// into `db` in the order of their paths, see [`import_addresses_parallel`] to use another sink.
 ///
 /// Example:
 ///
@@ -106,37 +427,11 @@ fn read_csv<P: AsRef<Path>, T: CompatibleDB>(db: &mut T, file_path: P) {
 /// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
 /// import_addresses("some_folder", &mut db);
 /// ```
-pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB>(base_path: P, db: &mut T) {
+pub fn import_addresses<P: AsRef<Path>, T: CompatibleDB + Send>(base_path: P, db: &mut T) {
     let count_before = db.get_nb_addresses();
-    let mut count_after = count_before;
-
-    let mut todo = vec![base_path.as_ref().to_path_buf()];
-
-    while let Some(path) = todo.pop() {
-        if path.is_dir() {
-            fs::read_dir(path)
-                .expect("folder not found")
-                .filter_map(|item| {
-                    item.map_err(|err| teprintln!("Failed to read path: {}", err))
-                        .ok()
-                })
-                .for_each(|item| todo.push(item.path()));
-        } else if path.extension().unwrap_or_else(|| OsStr::new("")) == "geojson" {
-            let short_name = path.strip_prefix(&base_path).unwrap_or(&path);
-            teprint!("[OA] Reading {:<40} ...\r", short_name.display());
-            read_csv(db, &path);
-
-            let new_count_after = db.get_nb_addresses();
-            teprintln!(
-                "[OA] Reading {:<40} ... {} addresses (total: {})",
-                short_name.display(),
-                new_count_after - count_after,
-                new_count_after
-            );
-
-            count_after = new_count_after;
-        }
-    }
+    let nb_threads = thread::available_parallelism().map_or(1, |n| n.get());
+    import_addresses_parallel(base_path, &LockedDB::new(db), nb_threads);
+    let count_after = db.get_nb_addresses();
 
     tprintln!(
         "[OA] Added {} addresses (total: {})",
@@ -166,4 +461,239 @@ mod tests {
 
         let _ = std::fs::remove_file(db_file); // we ignore any potential error
     }
+
+    #[test]
+    fn check_compressed() {
+        use std::io::Write;
+
+        let dir = std::env::temp_dir().join("check_oa_compressed");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let sample = std::fs::read("data/sample.geojson").unwrap();
+        let gzip = std::fs::File::create(dir.join("sample.geojson.gz")).unwrap();
+        let mut encoder = flate2::write::GzEncoder::new(gzip, Default::default());
+        encoder.write_all(&sample).unwrap();
+        encoder.finish().unwrap();
+
+        let db_file = "check_compressed.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        import_addresses(&dir, &mut db);
+        assert_eq!(db.get_nb_addresses(), 1000);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn check_tolerant() {
+        let data = r#"{"type":"Feature","properties":{"number":"1","street":"Rue A","unit":null},"geometry":{"type":"Point","coordinates":[2.0,48.0]}}
+{"type":"Feature","properties":{"number":2,"street":"Rue B","postcode":null},"geometry":{"type":"MultiPoint","coordinates":[[2.1,48.1],[2.2,48.2]]}}
+{"type":"Feature","properties":{"number":"3","street":"Rue C"},"geometry":{"type":"Polygon","coordinates":[[[2.0,48.0],[2.2,48.0],[2.2,48.2],[2.0,48.2],[2.0,48.0]]]}}
+{"type":"Feature","properties":{"number":"4","street":"Rue D"},"geometry":null}
+{"type":"Feature","properties":{"number":"5","street":"Rue E"},"geometry":{"type":"LineString","coordinates":[[2.0,48.0],[2.1,48.1]]}}
+{"type":"Feature","properties":{"number":"6","street":"Rue F"},"geometry":{"type":"Point","coordinates":[200.0,48.0]}}
+{"type":"Feature","properties":
+"#;
+
+        let db_file = "check_tolerant.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        let sink = LockedDB::new(&mut db);
+        let mut out = FileOutput::new(&sink);
+        read_geojson(Path::new("test.geojson"), &mut data.as_bytes(), &mut out);
+        out.flush();
+        let rejections = out.rejections;
+        assert_eq!(db.get_nb_addresses(), 3);
+
+        let addr = db.get_address(2, "Rue B");
+        assert_eq!((addr[0].lon, addr[0].lat), (2.1, 48.1));
+
+        let addr = db.get_address(3, "Rue C");
+        assert!((addr[0].lon - 2.1).abs() < 1e-9);
+        assert!((addr[0].lat - 48.1).abs() < 1e-9);
+
+        assert_eq!(
+            rejections.to_string(),
+            "1 invalid record, 1 missing geometry, 1 unsupported geometry, 1 invalid coordinates"
+        );
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_feature_collection() {
+        let data = r#"{
+  "type": "FeatureCollection",
+  "features": [
+    {
+      "type": "Feature",
+      "properties": {"number": "1", "street": "Rue A"},
+      "geometry": {"type": "Point", "coordinates": [2.0, 48.0]}
+    },
+    {
+      "type": "Feature",
+      "properties": "Rue B",
+      "geometry": {"type": "Point", "coordinates": [2.1, 48.1]}
+    },
+    {
+      "type": "Feature",
+      "properties": {"number": "3", "street": "Rue C"},
+      "geometry": {"type": "Point", "coordinates": [2.2, 48.2]}
+    }
+  ]
+}
+{"type":"Feature","properties":{"number":"4","street":"Rue D"},"geometry":{"type":"Point","coordinates":[2.3,48.3]}}
+"#;
+
+        let db_file = "check_feature_collection.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        let sink = LockedDB::new(&mut db);
+        let mut out = FileOutput::new(&sink);
+        read_geojson(Path::new("test.geojson"), &mut data.as_bytes(), &mut out);
+        out.flush();
+        let rejections = out.rejections;
+        assert_eq!(db.get_nb_addresses(), 3);
+        assert_eq!(db.get_address(3, "Rue C").len(), 1);
+        assert_eq!(db.get_address(4, "Rue D").len(), 1);
+        assert_eq!(rejections.to_string(), "1 invalid record");
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_legacy_csv() {
+        let dir = std::env::temp_dir().join("check_oa_legacy_csv");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        std::fs::write(
+            dir.join("legacy.csv"),
+            "LON,LAT,NUMBER,STREET,UNIT,CITY,DISTRICT,REGION,POSTCODE,ID,HASH\n\
+             2.3585,48.8427,12,Rue Buffon,,Paris,,,75005,a1,f00\n\
+             2.3586,invalid,14,Rue Buffon,,Paris,,,75005,a2,f01\n\
+             200,48.8427,16,Rue Buffon,,Paris,,,75005,a3,f02\n",
+        )
+        .unwrap();
+
+        let db_file = "check_legacy_csv.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        import_addresses(&dir, &mut db);
+        assert_eq!(db.get_nb_addresses(), 1);
+
+        let addr = db.get_address(12, "Rue Buffon");
+        assert_eq!(addr[0].source_id.as_deref(), Some("a1"));
+        assert_eq!(addr[0].unit, None);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn check_location() {
+        let dir = std::env::temp_dir().join("check_oa_location");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join("us/ca")).unwrap();
+
+        std::fs::write(
+            dir.join("us/ca/berkeley.geojson"),
+            r#"{"type":"Feature","properties":{"number":"1","street":"Main St","region":""},"geometry":{"type":"Point","coordinates":[-122.27,37.87]}}
+{"type":"Feature","properties":{"number":"2","street":"Main St","region":"NV"},"geometry":{"type":"Point","coordinates":[-122.27,37.87]}}
+"#,
+        )
+        .unwrap();
+
+        let db_file = "check_location.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+        import_addresses(&dir, &mut db);
+        assert_eq!(db.get_nb_addresses(), 2);
+
+        let addr = db.get_address(1, "Main St");
+        assert_eq!(addr[0].region.as_deref(), Some("CA"));
+        assert_eq!(addr[0].meta.country.as_deref(), Some("US"));
+        assert_eq!(addr[0].meta.source_file.as_deref(), Some("us/ca/berkeley"));
+
+        let addr = db.get_address(2, "Main St");
+        assert_eq!(addr[0].region.as_deref(), Some("NV"));
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn check_parallel() {
+        let dir = std::env::temp_dir().join("check_oa_parallel");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        for region in ["a", "b", "c", "d", "e"] {
+            std::fs::create_dir_all(dir.join("fr").join(region)).unwrap();
+            std::fs::copy(
+                "data/sample.geojson",
+                dir.join("fr").join(region).join("sample.geojson"),
+            )
+            .unwrap();
+        }
+
+        assert_eq!(list_files(&dir).len(), 5);
+        assert!(list_files(&dir).windows(2).all(|pair| pair[0] < pair[1]));
+
+        // Reading with several threads gives the same addresses as with a single one.
+        let mut counts = Vec::new();
+
+        for nb_threads in [1, 3] {
+            let db_file = "check_parallel.db";
+            let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+            let count = import_addresses_parallel(&dir, &LockedDB::new(&mut db), nb_threads);
+            assert_eq!(count, 5000);
+
+            let addr = db.get_address(38, "Allee du Chalam");
+            assert_eq!(addr.len(), 1);
+            assert!(addr
+                .iter()
+                .all(|addr| addr.meta.country.as_deref() == Some("FR")));
+            counts.push(db.get_nb_addresses());
+
+            let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        }
+
+        assert_eq!(counts[0], counts[1]);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+
+    #[test]
+    fn check_parallel_conflicts() {
+        let dir = std::env::temp_dir().join("check_oa_parallel_conflicts");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        // The first file is much longer than the following ones, which are read before it is done.
+        let row = r#"{"type":"Feature","properties":{"number":"1","street":"Rue A","city":"Paris"},"geometry":{"type":"Point","coordinates":[2.0,48.0]}}"#;
+        let mut sample = std::fs::read_to_string("data/sample.geojson").unwrap();
+        sample = format!("{}\n{}\n", sample.trim_end(), row);
+
+        for (region, content) in [
+            ("a", &sample),
+            ("b", &row.to_string()),
+            ("c", &row.to_string()),
+        ] {
+            std::fs::create_dir_all(dir.join("fr").join(region)).unwrap();
+            std::fs::write(dir.join("fr").join(region).join("x.geojson"), content).unwrap();
+        }
+
+        // The address of the first file is kept, whatever the number of threads.
+        for nb_threads in [1, 3] {
+            let db_file = "check_parallel_conflicts.db";
+            let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+            let count = import_addresses_parallel(&dir, &LockedDB::new(&mut db), nb_threads);
+            assert_eq!(count, 1003);
+            assert_eq!(db.get_nb_addresses(), 1001);
+
+            let addr = db.get_address(1, "Rue A");
+            assert_eq!(addr.len(), 1);
+            assert_eq!(addr[0].region.as_deref(), Some("A"));
+            assert_eq!(addr[0].meta.source_file.as_deref(), Some("fr/a/x"));
+
+            let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        }
+
+        let _ = std::fs::remove_dir_all(dir);
+    }
 }
@@ -0,0 +1,124 @@
+//! Country, region and source of a file, inferred from its path in the **OpenAddresses** tree,
+//! which is laid out as `<country>/<region>/<source>.geojson`.
+
+use std::path::{Component, Path};
+
+use smartstring::alias::String;
+use tools::{strip_compression_extension, Address};
+
+/// Top level folders which aren't named after the ISO 3166-1 alpha-2 code of a country, with the
+/// code they stand for, if any.
+const SPECIAL_FOLDERS: &[(&str, Option<&str>)] = &[
+    ("uk", Some("GB")),
+    ("el", Some("GR")),
+    ("summary", None),
+    ("world", None),
+    ("europe", None),
+];
+
+/// Location of a file in the **OpenAddresses** tree.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SourceLocation {
+    /// ISO 3166-1 alpha-2 code of the country, in upper case.
+    pub(crate) country: Option<String>,
+    /// Code of the region, in upper case, such as `CA` for `us/ca/san_francisco.geojson`.
+    pub(crate) region: Option<String>,
+    /// Path of the file in the tree without its extensions, such as `us/ca/san_francisco`.
+    pub(crate) source: Option<String>,
+}
+
+impl SourceLocation {
+    /// Infer the location of a file from its path, relative to the top of the tree.
+    pub(crate) fn from_path(path: &Path) -> Self {
+        let path = strip_compression_extension(path).with_extension("");
+
+        let parts: Vec<_> = path
+            .components()
+            .filter_map(|part| match part {
+                Component::Normal(part) => part.to_str(),
+                _ => None,
+            })
+            .collect();
+
+        let source = (!parts.is_empty()).then(|| parts.join("/").into());
+
+        // The last part is the file itself, the folders are the country then the region.
+        let (country, region) = match parts.as_slice() {
+            [country, region, _, ..] => (*country, Some(*region)),
+            [country, _] => (*country, None),
+            _ => {
+                return Self {
+                    source,
+                    ..Self::default()
+                }
+            }
+        };
+
+        let country = match SPECIAL_FOLDERS.iter().find(|(name, _)| *name == country) {
+            Some((_, code)) => code.map(Into::into),
+            None if country.len() == 2 => as_code(country),
+            None => None,
+        };
+
+        let region = region.and_then(as_code).filter(|_| country.is_some());
+
+        Self {
+            country,
+            region,
+            source,
+        }
+    }
+
+    /// Attach the location to an address: its country and source file are set, and its region is
+    /// filled if it is empty.
+    pub(crate) fn apply(&self, address: &mut Address) {
+        if address.region.is_none() {
+            address.region = self.region.clone();
+        }
+
+        address.meta.country = self.country.clone();
+        address.meta.source_file = self.source.clone();
+    }
+}
+
+/// Convert the name of a folder into a code: letters and digits only, in upper case.
+fn as_code(name: &str) -> Option<String> {
+    (!name.is_empty() && name.bytes().all(|c| c.is_ascii_alphanumeric()))
+        .then(|| name.to_ascii_uppercase().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn location(country: Option<&str>, region: Option<&str>, source: &str) -> SourceLocation {
+        SourceLocation {
+            country: country.map(Into::into),
+            region: region.map(Into::into),
+            source: Some(source.into()),
+        }
+    }
+
+    #[test]
+    fn check_from_path() {
+        let from_path = |path: &str| SourceLocation::from_path(Path::new(path));
+
+        assert_eq!(
+            from_path("us/ca/san_francisco.geojson"),
+            location(Some("US"), Some("CA"), "us/ca/san_francisco"),
+        );
+        assert_eq!(
+            from_path("fr/countrywide.geojson.gz"),
+            location(Some("FR"), None, "fr/countrywide"),
+        );
+        assert_eq!(
+            from_path("uk/ni/statewide.csv"),
+            location(Some("GB"), Some("NI"), "uk/ni/statewide"),
+        );
+        assert_eq!(
+            from_path("summary/sources.geojson"),
+            location(None, None, "summary/sources"),
+        );
+        assert_eq!(from_path("sample.geojson"), location(None, None, "sample"));
+    }
+}
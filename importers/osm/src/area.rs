@@ -0,0 +1,129 @@
+//! Area the imported addresses are clipped to, given as a bounding box or as polygons read from a
+//! GeoJSON file.
+
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+use geo::algorithm::bounding_rect::BoundingRect;
+use geo::algorithm::contains::Contains;
+use geo::{coord, Geometry, MultiPolygon, Point, Rect};
+use geojson::GeoJson;
+
+/// Only the addresses located inside of this area are imported.
+#[derive(Clone, Debug)]
+pub enum Area {
+    BBox(Rect<f64>),
+    Polygons {
+        shape: MultiPolygon<f64>,
+        /// Bounding box of the shape, checked first as it is much faster.
+        bbox: Rect<f64>,
+    },
+}
+
+impl Area {
+    /// Read the polygons of a GeoJSON file, which may contain a geometry, a feature or a
+    /// collection of features. All its geometries must be polygons or multipolygons.
+    pub fn from_geojson_file(path: &Path) -> Result<Self, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {:?}: {}", path, err))?;
+
+        let geometries: Vec<_> = match GeoJson::from_str(&content)
+            .map_err(|err| format!("invalid GeoJSON in {:?}: {}", path, err))?
+        {
+            GeoJson::Geometry(geometry) => vec![geometry],
+            GeoJson::Feature(feature) => feature.geometry.into_iter().collect(),
+            GeoJson::FeatureCollection(collection) => collection
+                .features
+                .into_iter()
+                .filter_map(|feature| feature.geometry)
+                .collect(),
+        };
+
+        let mut polygons = Vec::new();
+
+        for geometry in geometries {
+            match Geometry::try_from(geometry)
+                .map_err(|err| format!("invalid geometry in {:?}: {}", path, err))?
+            {
+                Geometry::Polygon(polygon) => polygons.push(polygon),
+                Geometry::MultiPolygon(multi) => polygons.extend(multi),
+                _ => return Err(format!("{:?} must only contain polygons", path)),
+            }
+        }
+
+        let shape = MultiPolygon(polygons);
+        let bbox = shape
+            .bounding_rect()
+            .ok_or_else(|| format!("{:?} doesn't contain any polygon", path))?;
+
+        Ok(Self::Polygons { shape, bbox })
+    }
+
+    /// Check if a location is inside of the area.
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        match self {
+            Self::BBox(bbox) => rect_contains(bbox, lat, lon),
+            Self::Polygons { shape, bbox } => {
+                rect_contains(bbox, lat, lon) && shape.contains(&Point::new(lon, lat))
+            }
+        }
+    }
+}
+
+/// Check if a location is inside of a rectangle, including its border.
+fn rect_contains(rect: &Rect<f64>, lat: f64, lon: f64) -> bool {
+    (rect.min().x..=rect.max().x).contains(&lon) && (rect.min().y..=rect.max().y).contains(&lat)
+}
+
+/// Parse a bounding box given as `min_lon,min_lat,max_lon,max_lat`.
+impl FromStr for Area {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let coords = s
+            .split(',')
+            .map(|coord| coord.trim().parse::<f64>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|err| format!("invalid bounding box `{}`: {}", s, err))?;
+
+        match coords[..] {
+            [min_lon, min_lat, max_lon, max_lat] if min_lon <= max_lon && min_lat <= max_lat => {
+                Ok(Self::BBox(Rect::new(
+                    coord! { x: min_lon, y: min_lat },
+                    coord! { x: max_lon, y: max_lat },
+                )))
+            }
+            _ => Err(format!(
+                "invalid bounding box `{}`, expected `min_lon,min_lat,max_lon,max_lat`",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_contains() {
+        let bbox: Area = "2.0,48.0,3.0,49.0".parse().unwrap();
+        assert!(bbox.contains(48.5, 2.5));
+        assert!(!bbox.contains(48.5, 3.5));
+        assert!("3.0,48.0,2.0,49.0".parse::<Area>().is_err());
+
+        let path = "check_contains.geojson";
+        fs::write(
+            path,
+            r#"{"type": "Polygon", "coordinates": [[[0, 0], [4, 0], [0, 4], [0, 0]]]}"#,
+        )
+        .unwrap();
+        let polygon = Area::from_geojson_file(path.as_ref()).unwrap();
+        let _ = fs::remove_file(path); // we ignore any potential error
+
+        assert!(polygon.contains(1., 1.));
+        assert!(!polygon.contains(3., 3.));
+        assert!(!polygon.contains(5., 1.));
+    }
+}
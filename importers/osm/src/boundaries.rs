@@ -0,0 +1,436 @@
+//! Administrative and postal code boundaries used to fill the fields that are missing on OSM
+//! addresses.
+//!
+//! Boundaries are extracted from the same input file as the addresses, in a dedicated pass made
+//! before the addresses are read:
+//!  * relations and closed ways tagged `boundary=administrative` give a city, a district or a
+//!    region name depending on their `admin_level` (see [`ADMIN_LEVELS`]).
+//!  * relations and closed ways tagged `boundary=postal_code` give a postcode through their
+//!    `postal_code` tag.
+//!
+//! Relations members roles are not kept by the importer, so rings are not split between "outer"
+//! and "inner": a point is considered inside of a boundary if a ray cast from it crosses the
+//! boundary's rings an odd number of times, which handles holes and enclaves the same way.
+//!
+//! To make this scale to a planet file, both the boundaries' bounding boxes and all their
+//! segments are stored in R-trees: a lookup only has to walk through the few segments that cross
+//! the ray between the point and the eastern edge of the candidate boundaries.
+
+use fxhash::{FxHashMap, FxHashSet};
+use osmpbfreader::objects::{OsmId, Tags};
+use osmpbfreader::OsmObj;
+use rstar::{RTree, RTreeObject, AABB};
+use smartstring::alias::String;
+
+use tools::Address;
+
+use crate::DepObj;
+
+/// Tags that must be kept on objects to be able to build boundaries from them.
+pub(crate) const BOUNDARY_TAGS_TO_KEEP: &[&str] =
+    &["boundary", "admin_level", "name", "postal_code"];
+
+/// Field of an address that can be filled by a boundary.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BoundaryKind {
+    City,
+    District,
+    Region,
+    Postcode,
+}
+
+/// Kind of the administrative boundaries for each `admin_level` that we care about. If several
+/// boundaries of the same kind contain an address, the one with the lowest level is used.
+pub const ADMIN_LEVELS: &[(u8, BoundaryKind)] = &[
+    (4, BoundaryKind::Region),
+    (8, BoundaryKind::City),
+    (9, BoundaryKind::District),
+    (10, BoundaryKind::District),
+];
+
+/// Returns the kind, level and value of a boundary from its tags, if these tags describe a
+/// boundary that can be used to fill an address.
+fn boundary_info(tags: &Tags) -> Option<(BoundaryKind, u8, String)> {
+    let get = |key: &str| tags.get(key);
+
+    match get("boundary")?.as_str() {
+        "administrative" => {
+            let level: u8 = get("admin_level")?.parse().ok()?;
+            let kind = ADMIN_LEVELS
+                .iter()
+                .find(|(lvl, _)| *lvl == level)
+                .map(|(_, kind)| *kind)?;
+            Some((kind, level, get("name")?.clone()))
+        }
+        "postal_code" => Some((BoundaryKind::Postcode, 0, get("postal_code")?.clone())),
+        _ => None,
+    }
+}
+
+/// Returns `true` if this object should be extracted from the input file to build boundaries.
+pub(crate) fn is_boundary(obj: &OsmObj) -> bool {
+    match obj {
+        OsmObj::Node(_) => false,
+        OsmObj::Way(w) => w.nodes.len() > 3 && w.is_closed() && boundary_info(&w.tags).is_some(),
+        OsmObj::Relation(r) => !r.refs.is_empty() && boundary_info(&r.tags).is_some(),
+    }
+}
+
+/// A boundary, without its geometry which is stored in the segments tree.
+#[derive(Debug)]
+struct Boundary {
+    kind: BoundaryKind,
+    level: u8,
+    value: String,
+}
+
+/// Bounding box of a boundary.
+struct BoundaryEnvelope {
+    boundary: usize,
+    envelope: AABB<[f64; 2]>,
+}
+
+impl RTreeObject for BoundaryEnvelope {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        self.envelope
+    }
+}
+
+/// A segment of one of the rings of a boundary, coordinates are stored as `[lon, lat]`.
+struct Segment {
+    boundary: usize,
+    start: [f64; 2],
+    end: [f64; 2],
+}
+
+impl Segment {
+    /// Check if an horizontal ray cast toward the east from `point` crosses this segment.
+    fn crosses_ray_from(&self, [x, y]: [f64; 2]) -> bool {
+        let ([x1, y1], [x2, y2]) = (self.start, self.end);
+
+        if (y1 > y) == (y2 > y) {
+            return false;
+        }
+
+        x1 + (y - y1) * (x2 - x1) / (y2 - y1) > x
+    }
+}
+
+impl RTreeObject for Segment {
+    type Envelope = AABB<[f64; 2]>;
+
+    fn envelope(&self) -> Self::Envelope {
+        AABB::from_corners(self.start, self.end)
+    }
+}
+
+/// Collects boundaries read from the input file, see [`BoundariesBuilder::build`] to get an index
+/// out of it.
+#[derive(Default)]
+pub struct BoundariesBuilder {
+    boundaries: Vec<Boundary>,
+    envelopes: Vec<BoundaryEnvelope>,
+    segments: Vec<Segment>,
+    seen: FxHashSet<OsmId>,
+}
+
+impl BoundariesBuilder {
+    /// Add an object which validated [`is_boundary`] together with its dependencies.
+    ///
+    /// Sub-relations (such as subareas) are added as well if they are boundaries themselves since
+    /// objects that are a dependency of another one are not handled on their own.
+    pub(crate) fn add(&mut self, obj: &DepObj) {
+        if !self.seen.insert(obj.root.id()) {
+            return;
+        }
+
+        for child in &obj.children {
+            if child.root.is_relation() {
+                self.add(child);
+            }
+        }
+
+        let (kind, level, value) = match boundary_info(obj.root.tags()) {
+            Some(info) => info,
+            None => return,
+        };
+
+        let ways = match &obj.root {
+            OsmObj::Node(_) => return,
+            OsmObj::Way(_) => vec![way_coords(obj)],
+            OsmObj::Relation(_) => obj
+                .children
+                .iter()
+                .filter(|child| child.root.is_way())
+                .map(way_coords)
+                .collect(),
+        };
+
+        let rings = assemble_rings(ways);
+
+        if rings.is_empty() {
+            return;
+        }
+
+        let boundary = self.boundaries.len();
+        let envelope = AABB::from_points(rings.iter().flatten());
+
+        self.boundaries.push(Boundary { kind, level, value });
+        self.envelopes.push(BoundaryEnvelope { boundary, envelope });
+
+        for ring in rings {
+            self.segments.extend(ring.windows(2).map(|pair| Segment {
+                boundary,
+                start: pair[0],
+                end: pair[1],
+            }));
+        }
+    }
+
+    /// Returns the number of boundaries collected so far.
+    pub fn len(&self) -> usize {
+        self.boundaries.len()
+    }
+
+    /// Build spatial indexes over the collected boundaries.
+    pub fn build(self) -> Boundaries {
+        Boundaries {
+            boundaries: self.boundaries,
+            envelopes: RTree::bulk_load(self.envelopes),
+            segments: RTree::bulk_load(self.segments),
+        }
+    }
+}
+
+/// Returns the coordinates of the nodes of a way as `[lon, lat]`.
+fn way_coords(way: &DepObj) -> Vec<[f64; 2]> {
+    way.children
+        .iter()
+        .filter_map(|child| child.root.node())
+        .map(|node| [node.lon(), node.lat()])
+        .collect()
+}
+
+/// Join ways by their ends until they form closed rings. Ways that can't be closed are dropped.
+fn assemble_rings(mut ways: Vec<Vec<[f64; 2]>>) -> Vec<Vec<[f64; 2]>> {
+    let mut rings = Vec::new();
+    ways.retain(|way| way.len() >= 2);
+
+    while let Some(mut ring) = ways.pop() {
+        while ring.first() != ring.last() {
+            let last = *ring.last().unwrap();
+            let next = ways
+                .iter()
+                .position(|way| way.first() == Some(&last) || way.last() == Some(&last));
+
+            match next {
+                Some(pos) => {
+                    let mut way = ways.swap_remove(pos);
+
+                    if way.first() != Some(&last) {
+                        way.reverse();
+                    }
+
+                    ring.extend(way.into_iter().skip(1));
+                }
+                None => break,
+            }
+        }
+
+        if ring.len() > 3 && ring.first() == ring.last() {
+            rings.push(ring);
+        }
+    }
+
+    rings
+}
+
+/// Spatial index over administrative and postal code boundaries.
+pub struct Boundaries {
+    boundaries: Vec<Boundary>,
+    envelopes: RTree<BoundaryEnvelope>,
+    segments: RTree<Segment>,
+}
+
+impl Boundaries {
+    /// Returns the boundaries containing the point at `lat`, `lon`.
+    fn containing(&self, lat: f64, lon: f64) -> impl Iterator<Item = &Boundary> {
+        let point = [lon, lat];
+        let candidates: Vec<_> = self
+            .envelopes
+            .locate_in_envelope_intersecting(&AABB::from_point(point))
+            .collect();
+
+        let ray_end = candidates
+            .iter()
+            .map(|candidate| candidate.envelope.upper()[0])
+            .fold(lon, f64::max);
+
+        let mut crossings: FxHashMap<usize, usize> = candidates
+            .iter()
+            .map(|candidate| (candidate.boundary, 0))
+            .collect();
+
+        for segment in self
+            .segments
+            .locate_in_envelope_intersecting(&AABB::from_corners(point, [ray_end, lat]))
+        {
+            if let Some(count) = crossings.get_mut(&segment.boundary) {
+                if segment.crosses_ray_from(point) {
+                    *count += 1;
+                }
+            }
+        }
+
+        crossings
+            .into_iter()
+            .filter(|(_, count)| count % 2 == 1)
+            .map(move |(boundary, _)| &self.boundaries[boundary])
+    }
+
+    /// Returns the name of the city containing the point at `lat`, `lon`.
+    pub(crate) fn city(&self, lat: f64, lon: f64) -> Option<String> {
+        self.containing(lat, lon)
+            .filter(|boundary| boundary.kind == BoundaryKind::City)
+            .min_by_key(|boundary| boundary.level)
+            .map(|boundary| boundary.value.clone())
+    }
+
+    /// Fill the empty city, district, region and postcode fields of an address with the
+    /// boundaries it is located in.
+    pub fn enrich(&self, address: &mut Address) {
+        if address.city.is_some()
+            && address.district.is_some()
+            && address.region.is_some()
+            && address.postcode.is_some()
+        {
+            return;
+        }
+
+        let mut found: Vec<_> = self.containing(address.lat, address.lon).collect();
+        found.sort_by_key(|boundary| boundary.level);
+
+        for boundary in found {
+            let field = match boundary.kind {
+                BoundaryKind::City => &mut address.city,
+                BoundaryKind::District => &mut address.district,
+                BoundaryKind::Region => &mut address.region,
+                BoundaryKind::Postcode => &mut address.postcode,
+            };
+
+            if field.is_none() {
+                *field = Some(boundary.value.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::Info;
+    use osmpbfreader::objects::{Node, NodeId, Ref, Relation, RelationId, Way, WayId};
+
+    fn node(id: i64, lon: f64, lat: f64) -> DepObj {
+        DepObj {
+            root: OsmObj::Node(Node {
+                id: NodeId(id),
+                tags: Tags::new(),
+                decimicro_lat: (lat * 1e7) as i32,
+                decimicro_lon: (lon * 1e7) as i32,
+            }),
+            info: Info::default(),
+            children: Vec::new(),
+        }
+    }
+
+    fn tags(kv: &[(&str, &str)]) -> Tags {
+        kv.iter().map(|(k, v)| ((*k).into(), (*v).into())).collect()
+    }
+
+    #[test]
+    fn check_enrich() {
+        // A square city split in two ways, with a hole in its middle.
+        let way_1 = DepObj {
+            root: OsmObj::Way(Way {
+                id: WayId(1),
+                tags: Tags::new(),
+                nodes: vec![NodeId(1), NodeId(2), NodeId(3)],
+            }),
+            info: Info::default(),
+            children: vec![node(1, 0., 0.), node(2, 4., 0.), node(3, 4., 4.)],
+        };
+        let way_2 = DepObj {
+            root: OsmObj::Way(Way {
+                id: WayId(2),
+                tags: Tags::new(),
+                nodes: vec![NodeId(1), NodeId(4), NodeId(3)],
+            }),
+            info: Info::default(),
+            children: vec![node(1, 0., 0.), node(4, 0., 4.), node(3, 4., 4.)],
+        };
+        let hole = DepObj {
+            root: OsmObj::Way(Way {
+                id: WayId(3),
+                tags: Tags::new(),
+                nodes: vec![NodeId(5), NodeId(6), NodeId(7), NodeId(8), NodeId(5)],
+            }),
+            info: Info::default(),
+            children: vec![
+                node(5, 1., 1.),
+                node(6, 2., 1.),
+                node(7, 2., 2.),
+                node(8, 1., 2.),
+                node(5, 1., 1.),
+            ],
+        };
+        let city = DepObj {
+            root: OsmObj::Relation(Relation {
+                id: RelationId(1),
+                tags: tags(&[
+                    ("boundary", "administrative"),
+                    ("admin_level", "8"),
+                    ("name", "Squareville"),
+                ]),
+                refs: vec![Ref {
+                    member: OsmId::Way(WayId(1)),
+                    role: "outer".into(),
+                }],
+            }),
+            info: Info::default(),
+            children: vec![way_1, way_2, hole],
+        };
+
+        let mut builder = BoundariesBuilder::default();
+        builder.add(&city);
+        assert_eq!(builder.len(), 1);
+        let boundaries = builder.build();
+
+        let mut inside = Address {
+            lat: 3.,
+            lon: 3.,
+            ..Address::default()
+        };
+        boundaries.enrich(&mut inside);
+        assert_eq!(inside.city.as_deref(), Some("Squareville"));
+
+        let mut in_hole = Address {
+            lat: 1.5,
+            lon: 1.5,
+            ..Address::default()
+        };
+        boundaries.enrich(&mut in_hole);
+        assert_eq!(in_hole.city, None);
+
+        let mut already_set = Address {
+            lat: 3.,
+            lon: 3.,
+            city: Some("Elsewhere".into()),
+            ..Address::default()
+        };
+        boundaries.enrich(&mut already_set);
+        assert_eq!(already_set.city.as_deref(), Some("Elsewhere"));
+    }
+}
@@ -0,0 +1,217 @@
+//! Application of **OsmChange** files (`.osc`, usually published compressed as `.osc.gz`) to a
+//! database previously filled by this importer, relying on the OSM id stored in the `source_id` of
+//! every address.
+//!
+//! The addresses of all created, modified and deleted nodes and ways are removed from the
+//! database, then created and modified objects are imported again following the same rules as a
+//! full import. A change file only contains the objects that changed, so:
+//!  * a way whose nodes are not all part of the change keeps its previous location, and moving
+//!    the nodes of a way doesn't update the location of its addresses;
+//!  * the members of a changed `associatedStreet` relation which are not part of the change get
+//!    their street updated, but objects removed from a relation are left untouched;
+//!  * boundaries are not available, the fields that were filled from boundaries during the full
+//!    import are lost for changed addresses.
+//!
+//! The same import options as for the full import should be given, so that changed objects are
+//! imported the same way. Only the boundaries, the node store and the handling of contained
+//! addresses are ignored.
+
+use std::path::Path;
+
+use fxhash::FxHashMap;
+use osmpbfreader::objects::{NodeId, OsmId};
+use osmpbfreader::OsmObj;
+use smartstring::alias::String;
+
+use tools::{tprintln, CompatibleDB, DB};
+
+use crate::info::Info;
+use crate::pipeline::Stage;
+use crate::reader::open_xml;
+use crate::xml::{to_decimicro, ChangeAction};
+use crate::{handle_obj, is_address, source_id, Context, DepObj, ImportConfig};
+
+/// Apply an **OsmChange** file to a database filled by [`import_addresses`].
+///
+/// * The `osc_file` argument is the location of the change file, it can be compressed with gzip
+///   (`.osc.gz`), bzip2 (`.osc.bz2`) or zstd.
+/// * The `db` argument is the database to update, changes must be applied in order.
+/// * The `config` argument holds the options used for the full import.
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::DB;
+/// use osm::{apply_changes, ImportConfig};
+///
+/// let mut db = DB::new("addresses.db", 10000, false).expect("failed to open DB");
+/// apply_changes("000.osc.gz".as_ref(), &mut db, &ImportConfig::default());
+/// ```
+///
+/// [`import_addresses`]: crate::import_addresses
+pub fn apply_changes(osc_file: &Path, db: &mut DB, config: &ImportConfig) {
+    let count_before = db.get_nb_addresses();
+
+    // Last version of all changed objects, an object may be changed several times in a file
+    let mut changes: Vec<(ChangeAction, OsmObj, Info)> = Vec::new();
+    let mut changes_index: FxHashMap<OsmId, usize> = FxHashMap::default();
+    let mut reader = open_xml(osc_file);
+
+    while let Some(obj) = reader.next() {
+        let (obj, info) =
+            obj.unwrap_or_else(|err| panic!("could not read OsmChange {:?}: {}", osc_file, err));
+
+        let action = reader.action().unwrap_or_else(|| {
+            panic!(
+                "{:?} is not in a create, modify or delete block of {:?}",
+                obj.id(),
+                osc_file
+            )
+        });
+
+        match changes_index.get(&obj.id()) {
+            Some(&index) => changes[index] = (action, obj, info),
+            None => {
+                changes_index.insert(obj.id(), changes.len());
+                changes.push((action, obj, info));
+            }
+        }
+    }
+
+    // Locations of the nodes which are part of the change
+    let nodes: FxHashMap<NodeId, (i32, i32)> = changes
+        .iter()
+        .filter_map(|(action, obj, _)| match obj {
+            OsmObj::Node(n) if *action != ChangeAction::Delete => {
+                Some((n.id, (n.decimicro_lat, n.decimicro_lon)))
+            }
+            _ => None,
+        })
+        .collect();
+
+    // Name of the changed relations each object is a member of
+    let mut members: FxHashMap<OsmId, Vec<String>> = FxHashMap::default();
+
+    for (action, obj, _) in &changes {
+        if *action == ChangeAction::Delete || !obj.is_relation() || !is_address(obj, config) {
+            continue;
+        }
+
+        let r = obj.relation().unwrap();
+
+        if let Some(name) = r.tags.get("name") {
+            for member in &r.refs {
+                if !member.member.is_relation() {
+                    members.entry(member.member).or_default().push(name.clone());
+                }
+            }
+        }
+    }
+
+    // Remove previous versions of the changed objects, keeping their location in case it can't
+    // be computed from the change.
+    let mut count_removed = 0;
+    let mut previous_locations: FxHashMap<OsmId, (f64, f64)> = FxHashMap::default();
+
+    for (_, obj, _) in changes.iter().filter(|(_, obj, _)| !obj.is_relation()) {
+        let removed = db.remove_by_source_id(&source_id(obj.id()));
+        count_removed += removed.len();
+
+        if let Some(addr) = removed.first() {
+            previous_locations.insert(obj.id(), (addr.lat, addr.lon));
+        }
+    }
+
+    // Update the street of relation members which didn't change
+    let mut count_renamed = 0;
+
+    for (member, streets) in &members {
+        if !changes_index.contains_key(member) {
+            for street in streets {
+                count_renamed += db.set_street_by_source_id(&source_id(*member), street);
+            }
+        }
+    }
+
+    // Import again created and modified objects
+    let mut ctx = Context {
+        db,
+        boundaries: None,
+        config,
+        count_outside: 0,
+        count_split: 0,
+        count_split_addresses: 0,
+        containment: None,
+        imported: None,
+        count_already_imported: 0,
+        count_streets: 0,
+        batch: Vec::new(),
+        conversion: Stage::default(),
+        insertion: Stage::default(),
+    };
+    let mut count_unresolved = 0;
+
+    for (action, obj, info) in changes {
+        if action == ChangeAction::Delete || obj.is_relation() {
+            continue;
+        }
+
+        let parents = members.remove(&obj.id());
+
+        if parents.is_none() && !is_address(&obj, config) {
+            continue;
+        }
+
+        let id = obj.id();
+        let mut obj: DepObj = (obj, info).into();
+
+        if let OsmObj::Way(w) = &obj.root {
+            let children: Option<Vec<_>> = w
+                .nodes
+                .iter()
+                .map(|node_id| {
+                    let (lat, lon) = nodes.get(node_id)?;
+                    Some(DepObj::node(*node_id, *lat, *lon))
+                })
+                .collect();
+
+            let children = children.or_else(|| {
+                let (lat, lon) = previous_locations.get(&id)?;
+                let node_id = w.nodes.first().copied().unwrap_or(NodeId(0));
+                Some(vec![DepObj::node(
+                    node_id,
+                    to_decimicro(*lat),
+                    to_decimicro(*lon),
+                )])
+            });
+
+            match children {
+                Some(children) if !children.is_empty() => obj.children = children,
+                _ => {
+                    count_unresolved += 1;
+                    continue;
+                }
+            }
+        }
+
+        match parents {
+            Some(parents) => {
+                for street in parents {
+                    handle_obj(obj.clone(), &mut ctx, Some(&street));
+                }
+            }
+            None => handle_obj(obj, &mut ctx, None),
+        }
+    }
+
+    let count_after = ctx.db.get_nb_addresses();
+    tprintln!(
+        "[OSM] Applied {:?}: {} addresses removed, {} added, {} renamed, {} ways without location (total: {})",
+        osc_file,
+        count_removed,
+        count_after + count_removed as i64 - count_before,
+        count_renamed,
+        count_unresolved,
+        count_after
+    );
+}
@@ -0,0 +1,181 @@
+//! Detection of address nodes located inside of a building which has the same address.
+//!
+//! It is common to find both a building tagged with `addr:*` and an address node inside of it
+//! carrying the same housenumber and street. Only one of them is kept, depending on
+//! [`ContainedAddresses`]. Nodes come before ways in OpenStreetMap files, so address nodes are
+//! already in the database when a building is inserted: the nodes located in its footprint are
+//! queried from the database, and either the building is skipped or the nodes are removed. Nothing
+//! is kept in memory between two buildings. Nodes only handled after their building, as members of
+//! an `associatedStreet` relation, are not compared with it.
+
+use std::str::FromStr;
+
+use geo::algorithm::contains::Contains;
+use geo::{BoundingRect, Point, Polygon};
+
+use tools::{Address, CompatibleDB};
+
+/// What to do with an address node located inside of a building with the same address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ContainedAddresses {
+    /// Import both the node and the building.
+    #[default]
+    KeepBoth,
+    /// Only import the node.
+    PreferNode,
+    /// Only import the building.
+    PreferBuilding,
+}
+
+impl FromStr for ContainedAddresses {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "keep-both" => Ok(Self::KeepBoth),
+            "prefer-node" => Ok(Self::PreferNode),
+            "prefer-building" => Ok(Self::PreferBuilding),
+            _ => Err(format!(
+                "unknown value `{}`, expected `keep-both`, `prefer-node` or `prefer-building`",
+                s
+            )),
+        }
+    }
+}
+
+/// Housenumber and street an address is compared with, ignoring case.
+type Key = (String, String);
+
+fn key(address: &Address) -> Option<Key> {
+    Some((
+        address.number.as_ref()?.to_lowercase(),
+        address.street.as_ref()?.to_lowercase(),
+    ))
+}
+
+/// Inserts addresses, dropping the nodes or buildings contained in (or containing) another
+/// address following [`ContainedAddresses`].
+pub(crate) struct Containment {
+    prefer: ContainedAddresses,
+    /// Number of addresses which were dropped
+    count_dropped: usize,
+}
+
+impl Containment {
+    pub(crate) fn new(prefer: ContainedAddresses) -> Option<Self> {
+        (prefer != ContainedAddresses::KeepBoth).then_some(Self {
+            prefer,
+            count_dropped: 0,
+        })
+    }
+
+    /// Insert an address read from a node, or from a building when `footprint` is set. The
+    /// address nodes of the database which are located in the footprint of a building and share
+    /// its address are removed with `PreferBuilding`, while the building is skipped with
+    /// `PreferNode`.
+    pub(crate) fn insert(
+        &mut self,
+        db: &mut impl CompatibleDB,
+        address: Address,
+        footprint: Option<&Polygon<f64>>,
+    ) {
+        let (Some(footprint), Some(building_key)) = (footprint, key(&address)) else {
+            db.insert(address);
+            return;
+        };
+
+        let Some(rect) = footprint.bounding_rect() else {
+            db.insert(address);
+            return;
+        };
+
+        let contained: Vec<_> = db
+            .get_addresses_in_bbox([rect.min().x, rect.min().y, rect.max().x, rect.max().y])
+            .into_iter()
+            .filter(|node| {
+                node.meta.object_kind.as_deref() == Some("node")
+                    && key(node).as_ref() == Some(&building_key)
+                    && footprint.contains(&Point::new(node.lon, node.lat))
+            })
+            .collect();
+
+        match self.prefer {
+            ContainedAddresses::PreferNode if !contained.is_empty() => self.count_dropped += 1,
+            ContainedAddresses::PreferBuilding => {
+                for node in &contained {
+                    db.remove(node);
+                }
+
+                self.count_dropped += contained.len();
+                db.insert(address);
+            }
+            _ => db.insert(address),
+        }
+    }
+
+    /// Number of addresses which were dropped so far.
+    pub(crate) fn count_dropped(&self) -> usize {
+        self.count_dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use geo::LineString;
+    use tools::{AddressMeta, DB};
+
+    fn address(kind: &str, number: &str, lat: f64, lon: f64) -> Address {
+        Address {
+            lat,
+            lon,
+            number: Some(number.into()),
+            street: Some("Rue Buffon".into()),
+            meta: AddressMeta {
+                object_kind: Some(kind.into()),
+                ..AddressMeta::default()
+            },
+            ..Address::default()
+        }
+    }
+
+    #[test]
+    fn check_containment() {
+        let footprint = Polygon::new(
+            LineString::from(vec![(0., 0.), (2., 0.), (2., 2.), (0., 2.), (0., 0.)]),
+            Vec::new(),
+        );
+
+        for prefer in [
+            ContainedAddresses::PreferNode,
+            ContainedAddresses::PreferBuilding,
+        ] {
+            let db_file = "check_containment.db";
+            let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+            let mut containment = Containment::new(prefer).unwrap();
+
+            containment.insert(&mut db, address("node", "1", 1., 1.), None);
+            containment.insert(&mut db, address("node", "2", 1., 1.), None);
+            containment.insert(&mut db, address("node", "1", 5., 5.), None);
+            containment.insert(&mut db, address("way", "1", 1.5, 1.), Some(&footprint));
+
+            assert_eq!(containment.count_dropped(), 1);
+            assert_eq!(db.get_nb_addresses(), 3);
+
+            let kept_kind = match prefer {
+                ContainedAddresses::PreferNode => "node",
+                _ => "way",
+            };
+
+            let kept = db.get_addresses_in_bbox([0., 0., 2., 2.]);
+            assert!(kept.iter().any(|addr| addr.number.as_deref() == Some("1")
+                && addr.meta.object_kind.as_deref() == Some(kept_kind)));
+            assert!(!kept.iter().any(|addr| addr.number.as_deref() == Some("1")
+                && addr.meta.object_kind.as_deref() != Some(kept_kind)));
+
+            let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        }
+
+        assert!(Containment::new(ContainedAddresses::KeepBoth).is_none());
+    }
+}
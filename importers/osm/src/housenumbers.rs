@@ -0,0 +1,69 @@
+//! Splitting of multi-valued housenumbers, such as `addr:housenumber=1;3;5`, into the numbers of
+//! separate addresses sharing the same location.
+//!
+//! Values are always split on semicolons, which is the usual way of tagging several values in
+//! OpenStreetMap. Numeric ranges such as `10-14` can also be expanded, but this is optional as a
+//! dash is also a part of many valid housenumbers (for instance `9-1` in Japan, or `1-3` for a
+//! building and its entrance in some countries). The numbers of a range are assumed to be on the
+//! same side of the street when both ends have the same parity: `10-14` gives `10`, `12` and `14`
+//! while `1-4` gives `1`, `2`, `3` and `4`.
+
+use smartstring::alias::String;
+
+/// Ranges spanning more numbers than this are kept as a single value, they are most likely not
+/// ranges of housenumbers.
+const MAX_RANGE_SIZE: u32 = 20;
+
+/// Split a housenumber into the list of numbers it stands for. A value which is not multi-valued
+/// is returned as is.
+pub(crate) fn split_housenumber(number: &str, expand_ranges: bool) -> Vec<String> {
+    let mut numbers = Vec::new();
+
+    for value in number.split(';').map(str::trim).filter(|v| !v.is_empty()) {
+        match expand_ranges.then(|| parse_range(value)).flatten() {
+            Some((start, end)) => {
+                let step = if (end - start) % 2 == 0 { 2 } else { 1 };
+                numbers.extend((start..=end).step_by(step).map(|n| n.to_string().into()));
+            }
+            None => numbers.push(value.into()),
+        }
+    }
+
+    numbers
+}
+
+/// Parse a range of numbers such as `10-14`.
+fn parse_range(value: &str) -> Option<(u32, u32)> {
+    let (start, end) = value.split_once('-')?;
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+
+    if !is_number(start.trim()) || !is_number(end.trim()) {
+        return None;
+    }
+
+    let start: u32 = start.trim().parse().ok()?;
+    let end: u32 = end.trim().parse().ok()?;
+
+    if start < end && end - start < MAX_RANGE_SIZE {
+        Some((start, end))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_split_housenumber() {
+        assert_eq!(split_housenumber("12 bis", true), ["12 bis"]);
+        assert_eq!(split_housenumber("1;3; 5;", false), ["1", "3", "5"]);
+        assert_eq!(split_housenumber("10-14", false), ["10-14"]);
+        assert_eq!(split_housenumber("10-14", true), ["10", "12", "14"]);
+        assert_eq!(split_housenumber("1-4;8", true), ["1", "2", "3", "4", "8"]);
+        assert_eq!(split_housenumber("14-10", true), ["14-10"]);
+        assert_eq!(split_housenumber("1-500", true), ["1-500"]);
+        assert_eq!(split_housenumber("1a-3", true), ["1a-3"]);
+    }
+}
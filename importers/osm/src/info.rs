@@ -0,0 +1,94 @@
+//! Version and time of the last edit of OSM objects.
+//!
+//! The objects of `osmpbfreader` don't hold this information, so readers return it next to each
+//! object, and it is carried with the objects which are selected by the import until addresses
+//! are built. As `osmpbfreader` also drops it while decoding PBF files, blocks are decoded here
+//! with the same parallel pipeline, and the information is read from the raw groups of the block.
+
+use osmpbfreader::fileformat::Blob;
+use osmpbfreader::objects::OsmObj;
+use osmpbfreader::osmformat::Info as PbfInfo;
+use osmpbfreader::{groups, primitive_block_from_blob};
+use time::{Format, OffsetDateTime};
+
+/// Version and timestamp (in seconds) of the last edit of an object.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Info {
+    pub version: Option<i64>,
+    pub timestamp: Option<i64>,
+}
+
+/// Decode the objects of a PBF blob, with their version and timestamp.
+pub(crate) fn read_pbf_blob(blob: Blob) -> Vec<(OsmObj, Info)> {
+    let block = primitive_block_from_blob(&blob).expect("could not read pbf");
+    let granularity = i64::from(block.get_date_granularity());
+    let to_seconds = |timestamp: i64| timestamp * granularity / 1000;
+
+    let pbf_info = |info: &PbfInfo| Info {
+        version: info.has_version().then(|| i64::from(info.get_version())),
+        timestamp: info
+            .has_timestamp()
+            .then(|| to_seconds(info.get_timestamp())),
+    };
+
+    let mut objs = Vec::new();
+
+    for group in block.get_primitivegroup() {
+        for (node, raw) in groups::simple_nodes(group, &block).zip(group.get_nodes()) {
+            objs.push((OsmObj::Node(node), pbf_info(raw.get_info())));
+        }
+
+        // Timestamps of dense nodes are delta-encoded
+        let dense_info = group.get_dense().get_denseinfo();
+        let versions = dense_info.get_version().iter().map(|v| Some(i64::from(*v)));
+        let timestamps = dense_info.get_timestamp().iter().scan(0, |current, delta| {
+            *current += delta;
+            Some(Some(to_seconds(*current)))
+        });
+        let dense_nodes = groups::dense_nodes(group, &block);
+
+        for (node, (version, timestamp)) in dense_nodes.zip(
+            versions
+                .zip(timestamps)
+                .chain(std::iter::repeat((None, None))),
+        ) {
+            objs.push((OsmObj::Node(node), Info { version, timestamp }));
+        }
+
+        for (way, raw) in groups::ways(group, &block).zip(group.get_ways()) {
+            objs.push((OsmObj::Way(way), pbf_info(raw.get_info())));
+        }
+
+        for (relation, raw) in groups::relations(group, &block).zip(group.get_relations()) {
+            objs.push((OsmObj::Relation(relation), pbf_info(raw.get_info())));
+        }
+    }
+
+    objs
+}
+
+/// Parse a timestamp of the OSM XML format, such as `2011-06-11T12:34:56Z`, into a number of
+/// seconds since the Unix epoch.
+pub(crate) fn parse_timestamp(value: &str) -> Option<i64> {
+    OffsetDateTime::parse(value, Format::Rfc3339)
+        .ok()
+        .map(OffsetDateTime::unix_timestamp)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_parse_timestamp() {
+        assert_eq!(parse_timestamp("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_timestamp("2011-06-11T12:34:56Z"), Some(1_307_795_696));
+        // Leap years, including centuries which are only leap years every 400 years
+        assert_eq!(parse_timestamp("2024-02-29T23:59:59Z"), Some(1_709_251_199));
+        assert_eq!(parse_timestamp("2000-02-29T00:00:00Z"), Some(951_782_400));
+        assert_eq!(parse_timestamp("2100-03-01T00:00:00Z"), Some(4_107_542_400));
+        assert_eq!(parse_timestamp("2100-02-29T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2023-02-29T00:00:00Z"), None);
+        assert_eq!(parse_timestamp("2011-06-11"), None);
+    }
+}
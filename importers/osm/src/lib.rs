@@ -1,4 +1,4 @@
-//! Reading a **PBF** file is actually complicated: elements refer to each others using IDs, forcing
+//! Reading a **PBF** (or **OSM XML**) file is actually complicated: elements refer to each others using IDs, forcing
 //! the parser to go back and forth in the file (unless you have a lot of available RAM!).
 //!
 //! So for this, we run it in 2 passes:
@@ -14,57 +14,126 @@
 //!    `associatedStreet` and at least one sub-reference. Then we read the sub-references an apply
 //!    the same rules depending if's a **node** or a **way**. We currently ignore the sub-references
 //!    if they are **relation**s.
+//!
+//! The housenumber and street of nodes and ways are read from tags which depend on their country,
+//! see [`TagSchemes`].
+//!
+//! When enabled through [`ImportConfig::boundaries`], administrative and postal code boundaries
+//! are read in an extra pass beforehand and used to fill the missing fields of the addresses.
+//!
+//! When memory is constrained, [`ImportConfig::node_store`] can be used to resolve objects
+//! differently: a first pass stores the locations of all nodes on disk, then a single other pass
+//! resolves ways and relations from this store.
+
+mod area;
+mod boundaries;
+mod changes;
+mod containment;
+mod housenumbers;
+mod info;
+mod node_store;
+mod pipeline;
+mod reader;
+mod streets;
+mod tag_schemes;
+mod xml;
 
-use std::fs::File;
-use std::io::{BufRead, BufReader, Seek};
 use std::path::Path;
+use std::time::Instant;
 
-use fxhash::FxHashMap;
+use fxhash::{FxHashMap, FxHashSet};
+use geo::algorithm::chamberlain_duquette_area::ChamberlainDuquetteArea;
+use geo::{LineString, Polygon};
 use geos::Geometry;
-use osmpbfreader::objects::{OsmId, Tags};
-use osmpbfreader::{OsmObj, OsmPbfReader};
+use osmpbfreader::objects::{Node, NodeId, OsmId, Tags};
+use osmpbfreader::OsmObj;
 use smartstring::alias::String;
 
-use tools::{teprint, tprintln, Address, CompatibleDB};
+use tools::{teprint, tprintln, Address, AddressMeta, CompatibleDB};
 
-/// Size of the read buffer put on top of the input PBF file
-const PBF_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+use crate::boundaries::{is_boundary, Boundaries, BoundariesBuilder, BOUNDARY_TAGS_TO_KEEP};
+use crate::containment::Containment;
+use crate::housenumbers::split_housenumber;
+use crate::info::Info;
+use crate::node_store::NodeStore;
+use crate::pipeline::{map_in_parallel, Stage};
+use crate::reader::{open_input, ObjReader};
+use crate::streets::{is_street, new_street};
+
+pub use crate::area::Area;
+pub use crate::changes::apply_changes;
+pub use crate::containment::ContainedAddresses;
+pub use crate::node_store::{NodeStoreConfig, NodeStoreKind};
+pub use crate::tag_schemes::TagSchemes;
 
 /// While reading the PBF, some objects for which members have not been fetched yet are kept into
 /// memory. This is the maximal number of objects that are explicitly loaded from the PBF, value 5M
 /// usually leads to less than 10GB of ram usage.
 const MAX_PENDING_OBJECTS: usize = 5_000_000;
 
-/// Used to make the stored elements in the first lighter by removing all the unused tags.
+/// Number of objects converted into addresses at once, spread over all cores. Threads are spawned
+/// for each batch, so it is large enough for this cost to be negligible.
+const CONVERSION_BATCH_SIZE: usize = 100_000;
+
+/// Number of objects filtered at once, or of ways whose nodes are read from the node store at once,
+/// spread over all cores. Raw objects are much lighter than converted ones, and this stays well
+/// below [`MAX_PENDING_OBJECTS`].
+const RESOLUTION_BATCH_SIZE: usize = 1_000_000;
+
+/// Used to make the stored elements in the first lighter by removing all the unused tags. Nodes and
+/// ways also keep all their `addr:*` tags, which may be used by tag schemes.
 const REL_TAGS_TO_KEEP: &[&str] = &["name"];
-const WAY_TAGS_TO_KEEP: &[&str] = &["addr:housenumber", "addr:street"];
-const NODE_TAGS_TO_KEEP: &[&str] = &[
-    "addr:housenumber",
-    "addr:street",
-    "addr:unit",
-    "addr:city",
-    "addr:district",
-    "addr:region",
-    "addr:postcode",
-    "name",
-];
+const WAY_TAGS_TO_KEEP: &[&str] = &["name", "building"];
+const NODE_TAGS_TO_KEEP: &[&str] = &["name", "building", "entrance"];
 
 const MAX_VALID_HOUSENUMBER_LENGTH: usize = 8;
 
+/// Options of the **OpenStreetMap** importer.
+#[derive(Clone, Debug, Default)]
+pub struct ImportConfig {
+    /// Fill the missing city, district, region and postcode of addresses using the boundaries
+    /// available in the input file.
+    pub boundaries: bool,
+    /// Store node locations on disk and resolve ways and relations in a single extra pass,
+    /// instead of keeping pending objects in memory.
+    pub node_store: Option<NodeStoreConfig>,
+    /// Only import the addresses located inside of this area. The location of ways and relations
+    /// is the one computed for their addresses, not the location of their nodes.
+    pub area: Option<Area>,
+    /// Mapping of tags to the fields of addresses, depending on the country of objects.
+    pub tag_schemes: TagSchemes,
+    /// Country code used to select the tag scheme of objects without an `addr:country` tag.
+    pub country: Option<std::string::String>,
+    /// Expand numeric ranges of housenumbers, such as `10-14`, into separate addresses. Lists
+    /// such as `1;3;5` are always split.
+    pub expand_ranges: bool,
+    /// What to do with address nodes located inside of a building with the same address. The nodes
+    /// are looked up with [`CompatibleDB::get_addresses_in_bbox`] when a building is inserted.
+    pub contained_addresses: ContainedAddresses,
+    /// Also extract named streets (`highway=*` ways with a `name`), whether or not they have
+    /// addresses. They are inserted with [`CompatibleDB::insert_street`], which the inserter of
+    /// the deduplicator doesn't implement as it only outputs addresses, so it never sets this.
+    pub streets: bool,
+    /// Number of threads the filtering, the resolution and the conversion of objects are spread
+    /// over, all the available cores are used by default.
+    pub nb_threads: Option<usize>,
+}
+
 /// Convert an element's tags into an address.
 ///
-/// In here, we look at the following tags:
+/// The tags used depend on the country of the element, see [`TagSchemes`]. By default, we look
+/// at the following tags:
 ///  * `addr:housenumber`
 ///  * `addr:street`
 ///  * `addr:unit`
 ///  * `addr:city`
-///  * `addr:district`
-///  * `addr:region`
+///  * `addr:district` (or `addr:suburb`)
+///  * `addr:region` (or `addr:province`)
 ///  * `addr:postcode`
 ///
-/// This might evolve in the future considering that some countries use different tags to store the
-/// same information.
-fn new_address(tags: Tags, lat: f64, lon: f64) -> Address {
+/// The `name`, `building` and `entrance` tags, as well as the version and the timestamp of the
+/// element, are kept as metadata of the address.
+fn new_address(tags: &Tags, info: Info, lat: f64, lon: f64, config: &ImportConfig) -> Address {
     let mut addr = Address {
         lat,
         lon,
@@ -75,34 +144,20 @@ fn new_address(tags: Tags, lat: f64, lon: f64) -> Address {
         district: None,
         region: None,
         postcode: None,
+        source_id: None,
+        meta: AddressMeta {
+            name: tags.get("name").cloned(),
+            building: tags.get("building").cloned(),
+            entrance: tags.get("entrance").cloned(),
+            version: info.version,
+            timestamp: info.timestamp,
+            ..AddressMeta::default()
+        },
     };
 
-    for (tag, value) in tags.into_inner() {
-        match tag.as_str() {
-            "addr:housenumber" => {
-                addr.number = Some(value);
-            }
-            "addr:street" => {
-                addr.street = Some(value);
-            }
-            "addr:unit" => {
-                addr.unit = Some(value);
-            }
-            "addr:city" => {
-                addr.city = Some(value);
-            }
-            "addr:district" => {
-                addr.district = Some(value);
-            }
-            "addr:region" => {
-                addr.region = Some(value);
-            }
-            "addr:postcode" => {
-                addr.postcode = Some(value);
-            }
-            _ => {}
-        }
-    }
+    config
+        .tag_schemes
+        .fill(&mut addr, tags, config.country.as_deref());
     addr
 }
 
@@ -110,10 +165,63 @@ fn new_address(tags: Tags, lat: f64, lon: f64) -> Address {
 #[derive(Clone, Debug)]
 struct DepObj {
     root: OsmObj,
+    /// Version and timestamp of the root object
+    info: Info,
     children: Vec<DepObj>,
 }
 
 impl DepObj {
+    /// Build a node without tags, used as the child of a way when only its location is known.
+    fn node(id: NodeId, decimicro_lat: i32, decimicro_lon: i32) -> Self {
+        Self {
+            root: OsmObj::Node(Node {
+                id,
+                tags: Tags::new(),
+                decimicro_lat,
+                decimicro_lon,
+            }),
+            info: Info::default(),
+            children: Vec::new(),
+        }
+    }
+
+    /// Convert an object, stripping the tags which are not used to build addresses, except for
+    /// `extra_tags`.
+    fn with_tags(mut obj: OsmObj, info: Info, extra_tags: &[&str]) -> Self {
+        let (tags_to_keep, keep_addr) = match obj {
+            OsmObj::Node(_) => (NODE_TAGS_TO_KEEP, true),
+            OsmObj::Way(_) => (WAY_TAGS_TO_KEEP, true),
+            OsmObj::Relation(_) => (REL_TAGS_TO_KEEP, false),
+        };
+
+        let tags = match &mut obj {
+            OsmObj::Node(n) => &mut n.tags,
+            OsmObj::Way(w) => &mut w.tags,
+            OsmObj::Relation(r) => &mut r.tags,
+        };
+
+        tags.retain(|k, _| {
+            (keep_addr && k.starts_with("addr:"))
+                || tags_to_keep.contains(&k.as_str())
+                || extra_tags.contains(&k.as_str())
+        });
+        tags.shrink_to_fit();
+
+        let max_children = {
+            match &obj {
+                OsmObj::Node(_) => 0,
+                OsmObj::Way(w) => w.nodes.len(),
+                OsmObj::Relation(r) => r.refs.len(),
+            }
+        };
+
+        Self {
+            root: obj,
+            info,
+            children: Vec::with_capacity(max_children),
+        }
+    }
+
     /// Check if all the children for this node have been extracted from OSM file.
     fn is_complete(&self) -> bool {
         self.children.len() == self.children.capacity()
@@ -167,35 +275,9 @@ impl DepObj {
     }
 }
 
-impl From<OsmObj> for DepObj {
-    fn from(mut obj: OsmObj) -> Self {
-        let tags_to_keep = match obj {
-            OsmObj::Node(_) => NODE_TAGS_TO_KEEP,
-            OsmObj::Way(_) => WAY_TAGS_TO_KEEP,
-            OsmObj::Relation(_) => REL_TAGS_TO_KEEP,
-        };
-
-        let tags = match &mut obj {
-            OsmObj::Node(n) => &mut n.tags,
-            OsmObj::Way(w) => &mut w.tags,
-            OsmObj::Relation(r) => &mut r.tags,
-        };
-
-        tags.retain(|k, _| tags_to_keep.contains(&k.as_str()));
-        tags.shrink_to_fit();
-
-        let max_children = {
-            match &obj {
-                OsmObj::Node(_) => 0,
-                OsmObj::Way(w) => w.nodes.len(),
-                OsmObj::Relation(r) => r.refs.len(),
-            }
-        };
-
-        Self {
-            root: obj,
-            children: Vec::with_capacity(max_children),
-        }
+impl From<(OsmObj, Info)> for DepObj {
+    fn from((obj, info): (OsmObj, Info)) -> Self {
+        Self::with_tags(obj, info, &[])
     }
 }
 
@@ -205,18 +287,21 @@ fn object_depth(obj_id: OsmId, deps_graph: &FxHashMap<OsmId, (u8, Vec<OsmId>)>)
     deps_graph.get(&obj_id).map(|x| x.0).unwrap_or(1)
 }
 
-/// Fetch all objects from input PBF reader. The objects that validate the function `filter_obj`
-/// will be passed into `handle_obj` with their dependencies.
+/// Fetch all objects from input reader. The objects that validate the function `filter_obj`
+/// will be passed into `handle_obj` with their dependencies. The tags which are not used to build
+/// addresses are stripped from objects, except for `extra_tags`.
 ///
 /// This is done by maintaining a list of objects into memory together with the graph of the
 /// objects they depend on. The PBF files will be read sequentially several times until all
 /// dependencies are resolved. To preserve from very high memory usage, the objects are deallocated
 /// as soon as all their dependencies are resolved.
-fn fetch_objects<R: BufRead + Seek, T: CompatibleDB>(
+fn fetch_objects(
     max_depth: u8,
-    reader: &mut OsmPbfReader<R>,
-    filter_obj: impl Fn(&OsmObj) -> bool,
-    db: &mut T,
+    nb_threads: Option<usize>,
+    reader: &mut dyn ObjReader,
+    extra_tags: &[&str],
+    filter_obj: impl Fn(&OsmObj) -> bool + Sync,
+    mut handle_obj: impl FnMut(DepObj),
 ) {
     // Simple counter for objects extracted so far
     let mut count_objs: u64 = 0;
@@ -240,102 +325,275 @@ fn fetch_objects<R: BufRead + Seek, T: CompatibleDB>(
     while made_progress {
         teprint!("Build graph layer ... ");
         made_progress = false;
-        reader.rewind().expect("could not rewind PBF reader");
-
-        'read_pbf: for obj in reader.par_iter() {
-            let obj = obj.expect("could not read pbf");
-
-            // The first layer only consists of filtered objects. Next layers include objects that
-            // are required by dependency and not yet pending
-            let feasible = (import_first_layer && filter_obj(&obj))
-                || (deps_graph.contains_key(&obj.id()) && !pending.contains_key(&obj.id()));
-
-            // Keep track of the last explicitly imported object while import_first_layer is true.
-            // Then, it is set to true when the run reaches a section of the file that is not
-            // imported yet.
-            if import_first_layer {
-                last_imported_object = Some(obj.id());
-            } else if last_imported_object == Some(obj.id()) {
-                import_first_layer = true;
-            }
 
-            if !feasible {
-                continue;
+        // Objects are decoded in parallel, then filtered and stripped of their unused tags by
+        // batches spread over all cores. The graph is built on this thread, in the order of the
+        // file.
+        let start = Instant::now();
+        let mut count_read = 0;
+        let mut objects = reader.objects();
+
+        'read_pbf: loop {
+            let batch: Vec<_> = objects.by_ref().take(RESOLUTION_BATCH_SIZE).collect();
+
+            if batch.is_empty() {
+                break;
             }
 
-            // Convert into internal object format
-            let obj: DepObj = obj.into();
-            made_progress = true;
-
-            // Create dependencies to this object, if they are within selected depth
-            if object_depth(obj.root.id(), &deps_graph) < max_depth {
-                for child in obj.expected_children() {
-                    deps_graph
-                        .entry(child)
-                        .or_insert_with(|| (1, Vec::new()))
-                        .1
-                        .push(obj.root.id());
+            count_read += batch.len();
+
+            // Objects are only selected by the filter in the first layer, which starts again
+            // after the last imported object.
+            let may_select = import_first_layer
+                || batch
+                    .iter()
+                    .any(|(obj, _)| last_imported_object == Some(obj.id()));
+
+            // Objects which are not required yet are left as read, as they may become the
+            // dependency of an object of the same batch.
+            let batch = map_in_parallel(nb_threads, batch, |(obj, info)| {
+                let selected = may_select && filter_obj(&obj);
+
+                if selected || deps_graph.contains_key(&obj.id()) {
+                    (Ok(DepObj::with_tags(obj, info, extra_tags)), selected)
+                } else {
+                    (Err((obj, info)), selected)
+                }
+            });
+
+            for (obj, selected) in batch {
+                let id = match &obj {
+                    Ok(obj) => obj.root.id(),
+                    Err((obj, _)) => obj.id(),
+                };
+
+                // The first layer only consists of filtered objects. Next layers include objects
+                // that are required by dependency and not yet pending
+                let feasible = (import_first_layer && selected)
+                    || (deps_graph.contains_key(&id) && !pending.contains_key(&id));
+
+                // Keep track of the last explicitly imported object while import_first_layer is
+                // true. Then, it is set to true when the run reaches a section of the file that is
+                // not imported yet.
+                if import_first_layer {
+                    last_imported_object = Some(id);
+                } else if last_imported_object == Some(id) {
+                    import_first_layer = true;
                 }
-            }
 
-            // Start a graph search from current node, which propagate on completed objects
-            let mut todo = vec![obj];
-
-            while let Some(mut obj) = todo.pop() {
-                if obj.is_complete() || object_depth(obj.root.id(), &deps_graph) == max_depth {
-                    obj.reorder_and_cleanup_children();
-
-                    if let Some((_, parents_id)) = deps_graph.remove(&obj.root.id()) {
-                        // Insert the object in its parents
-                        for parent_id in parents_id {
-                            // Fetch parent from pending objects. Occasionally it is dependency of
-                            // two objects and will already be in the `todo` heap.
-                            let parent_obj = {
-                                if let Some(parent_obj) = pending.remove(&parent_id) {
-                                    todo.push(parent_obj);
-                                    todo.last_mut().unwrap()
-                                } else {
-                                    todo.iter_mut().find(|x| x.root.id() == parent_id).unwrap()
-                                }
-                            };
-
-                            // Note that this clone should be OK because repeating child will
-                            // normally only happen for closed ways.
-                            parent_obj.children.push(obj.clone());
+                if !feasible {
+                    continue;
+                }
+
+                // Convert into internal object format, unless it was done in parallel
+                let obj: DepObj =
+                    obj.unwrap_or_else(|(obj, info)| DepObj::with_tags(obj, info, extra_tags));
+                made_progress = true;
+
+                // Create dependencies to this object, if they are within selected depth
+                if object_depth(obj.root.id(), &deps_graph) < max_depth {
+                    for child in obj.expected_children() {
+                        deps_graph
+                            .entry(child)
+                            .or_insert_with(|| (1, Vec::new()))
+                            .1
+                            .push(obj.root.id());
+                    }
+                }
+
+                // Start a graph search from current node, which propagate on completed objects
+                let mut todo = vec![obj];
+
+                while let Some(mut obj) = todo.pop() {
+                    if obj.is_complete() || object_depth(obj.root.id(), &deps_graph) == max_depth {
+                        obj.reorder_and_cleanup_children();
+
+                        if let Some((_, parents_id)) = deps_graph.remove(&obj.root.id()) {
+                            // Insert the object in its parents
+                            for parent_id in parents_id {
+                                // Fetch parent from pending objects. Occasionally it is dependency of
+                                // two objects and will already be in the `todo` heap.
+                                let parent_obj = {
+                                    if let Some(parent_obj) = pending.remove(&parent_id) {
+                                        todo.push(parent_obj);
+                                        todo.last_mut().unwrap()
+                                    } else {
+                                        todo.iter_mut().find(|x| x.root.id() == parent_id).unwrap()
+                                    }
+                                };
+
+                                // Note that this clone should be OK because repeating child will
+                                // normally only happen for closed ways.
+                                parent_obj.children.push(obj.clone());
+                            }
+                        } else {
+                            // If this object has no parents it means that it was selected by input
+                            // filter and must be handled
+                            handle_obj(obj);
+                            count_objs += 1;
                         }
                     } else {
-                        // If this object has no parents it means that it was selected by input
-                        // filter and must be handled
-                        handle_obj(obj, db, None);
-                        count_objs += 1;
+                        pending.insert(obj.root.id(), obj);
                     }
-                } else {
-                    pending.insert(obj.root.id(), obj);
                 }
-            }
 
-            // Reset run if too many items are in dependencies
-            if import_first_layer && pending.len() >= MAX_PENDING_OBJECTS {
-                break 'read_pbf;
+                // Reset run if too many items are in dependencies
+                if import_first_layer && pending.len() >= MAX_PENDING_OBJECTS {
+                    break 'read_pbf;
+                }
             }
         }
 
         import_first_layer = false;
+        let mut read = Stage::default();
+        read.add(count_read, start.elapsed());
 
         if made_progress && !pending.is_empty() {
             eprintln!(
-                "{} pending, {} deps, objs: {}",
+                "{} pending, {} deps, objs: {}, read: {}",
                 pending.len(),
                 deps_graph.len(),
-                count_objs
+                count_objs,
+                read
             );
         } else {
-            eprintln!("done");
+            eprintln!("done, read: {}", read);
             break;
         }
     }
 }
 
+/// Same as [`fetch_objects`], but relying on a [`NodeStore`] to resolve dependencies. This only
+/// handles objects up to depth 3 (relations of ways of nodes) and `handle_obj` is called with the
+/// name of the parent relation for the objects that are members of a relation validating
+/// `filter_obj`, instead of being called on the relation itself.
+///
+/// The input is read exactly twice:
+///  1. Locations of all nodes are written in the store, and the members of filtered relations are
+///     collected.
+///  2. Filtered nodes and ways, and members of filtered relations are resolved using the store
+///     and handled.
+fn fetch_objects_with_store(
+    store_config: &NodeStoreConfig,
+    nb_threads: Option<usize>,
+    reader: &mut dyn ObjReader,
+    filter_obj: impl Fn(&OsmObj) -> bool,
+    mut handle_obj: impl FnMut(DepObj, Option<&str>),
+) {
+    let mut store = NodeStore::create(store_config).unwrap_or_else(|err| {
+        panic!(
+            "Failed to create node store {:?}: {}",
+            store_config.path, err
+        )
+    });
+
+    // Name of the relations each object is a member of
+    let mut members: FxHashMap<OsmId, Vec<String>> = FxHashMap::default();
+
+    teprint!("Store nodes locations ... ");
+
+    for (obj, _) in reader.objects() {
+        match obj {
+            OsmObj::Node(n) => store
+                .insert(n.id, n.decimicro_lat, n.decimicro_lon)
+                .expect("failed to write into node store"),
+            OsmObj::Way(_) => {}
+            obj @ OsmObj::Relation(_) if filter_obj(&obj) => {
+                let r = obj.relation().unwrap();
+
+                if let Some(name) = r.tags.get("name") {
+                    for member in &r.refs {
+                        if !member.member.is_relation() {
+                            members.entry(member.member).or_default().push(name.clone());
+                        }
+                    }
+                }
+            }
+            OsmObj::Relation(_) => {}
+        }
+    }
+
+    store.finish().expect("failed to finish node store");
+    eprintln!("{} nodes, {} relation members", store.len(), members.len());
+
+    teprint!("Resolve objects ... ");
+    let mut count_objs: u64 = 0;
+    let mut count_missing: u64 = 0;
+    let mut resolution = Stage::default();
+    let mut objects = reader.objects();
+
+    loop {
+        // Objects are selected in the order of the file, then their nodes are read from the
+        // store in parallel
+        let batch: Vec<_> = objects
+            .by_ref()
+            .filter(|(obj, _)| !obj.is_relation())
+            .filter_map(|(obj, info)| {
+                let parents = members.remove(&obj.id());
+                (parents.is_some() || filter_obj(&obj)).then_some((obj, info, parents))
+            })
+            .take(RESOLUTION_BATCH_SIZE)
+            .collect();
+
+        if batch.is_empty() {
+            break;
+        }
+
+        let count_batch = batch.len();
+        let start = Instant::now();
+        let resolved = map_in_parallel(nb_threads, batch, |(obj, info, parents)| {
+            (resolve_from_store((obj, info).into(), &store), parents)
+        });
+        resolution.add(count_batch, start.elapsed());
+
+        for ((obj, missing), parents) in resolved {
+            count_missing += missing;
+
+            if obj.root.is_way() && obj.children.is_empty() {
+                continue;
+            }
+
+            count_objs += 1;
+
+            match parents {
+                Some(parents) => {
+                    for street in parents {
+                        handle_obj(obj.clone(), Some(&street));
+                    }
+                }
+                None => handle_obj(obj, None),
+            }
+        }
+    }
+
+    eprintln!(
+        "objs: {}, missing nodes: {}, resolved: {}",
+        count_objs, count_missing, resolution
+    );
+}
+
+/// Fill the nodes of a way with their locations read from the store. Returns the object with the
+/// number of nodes which are missing from the store.
+fn resolve_from_store(mut obj: DepObj, store: &NodeStore) -> (DepObj, u64) {
+    let mut count_missing = 0;
+
+    if let OsmObj::Way(w) = &obj.root {
+        for node_id in &w.nodes {
+            match store.get(*node_id) {
+                Some((decimicro_lat, decimicro_lon)) => {
+                    obj.children
+                        .push(DepObj::node(*node_id, decimicro_lat, decimicro_lon))
+                }
+                None => count_missing += 1,
+            }
+        }
+
+        obj.children.shrink_to_fit();
+    }
+
+    (obj, count_missing)
+}
+
 /// Function to generate a position for a **way**. If the **way** is only composed of one **node**,
 /// it'll return the latitude and longitude of this **node**. If there is more than one, it'll first
 /// create a polygon and then get its centroid's latitude and longitude.
@@ -373,22 +631,191 @@ fn get_way_lat_lon(sub_objs: &[DepObj]) -> Option<(f64, f64)> {
     None
 }
 
-/// Function used in the "first pass" by the [`iter_nodes`] function.
-///
-/// The goal here is to filter out all the elements that don't seem to be addresses and store the
-/// others into the provided `db` argument.
-///
-/// The conditions are explained at the crate level.
-fn handle_obj<T: CompatibleDB>(obj: DepObj, db: &mut T, override_street: Option<&str>) {
+/// Footprint of a closed **way**.
+fn get_way_footprint(sub_objs: &[DepObj]) -> Option<Polygon<f64>> {
+    let nodes: Vec<_> = sub_objs.iter().filter_map(|x| x.root.node()).collect();
+
+    match nodes[..] {
+        [first, .., last] if nodes.len() >= 4 && first.id == last.id => {
+            let ring: LineString<f64> = nodes.iter().map(|n| (n.lon(), n.lat())).collect();
+            Some(Polygon::new(ring, Vec::new()))
+        }
+        _ => None,
+    }
+}
+
+/// State shared by all the objects handled during an import.
+struct Context<'a, T> {
+    db: &'a mut T,
+    boundaries: Option<Boundaries>,
+    config: &'a ImportConfig,
+    /// Number of addresses ignored because they are outside of `config.area`
+    count_outside: u64,
+    /// Number of objects with a multi-valued housenumber, and of the addresses they were split in
+    count_split: u64,
+    count_split_addresses: u64,
+    /// Set when `config.contained_addresses` drops some of the nodes or buildings
+    containment: Option<Containment>,
+    /// Objects imported from previous files, when several files are imported. An object is
+    /// identified by its id and the hash of the street it is imported with, as members of
+    /// relations may be handled with the name of their relation.
+    imported: Option<&'a mut FxHashSet<(OsmId, u64)>>,
+    count_already_imported: u64,
+    /// Number of streets extracted, when `config.streets` is set
+    count_streets: u64,
+    /// Objects waiting to be converted into addresses, with the street they are imported with
+    batch: Vec<(DepObj, Option<String>)>,
+    /// Throughput of the conversion of objects into addresses, and of the insertion of addresses
+    conversion: Stage,
+    insertion: Stage,
+}
+
+impl<T: CompatibleDB> Context<'_, T> {
+    /// Handle an object selected by the import, unless it was already imported.
+    fn handle_new_obj(&mut self, obj: DepObj, street: Option<&str>) {
+        if let Some(imported) = &mut self.imported {
+            let street_hash = street.map_or(0, fxhash::hash64);
+
+            if !imported.insert((obj.root.id(), street_hash)) {
+                self.count_already_imported += 1;
+                return;
+            }
+        }
+
+        self.batch.push((obj, street.map(Into::into)));
+
+        if self.batch.len() >= CONVERSION_BATCH_SIZE {
+            self.flush();
+        }
+    }
+
+    /// Convert the pending objects into addresses using all cores, then insert them in the order
+    /// the objects were read.
+    fn flush(&mut self) {
+        if self.batch.is_empty() {
+            return;
+        }
+
+        let batch = std::mem::take(&mut self.batch);
+        let count_objs = batch.len();
+        let (config, boundaries) = (self.config, self.boundaries.as_ref());
+
+        let start = Instant::now();
+        let converted = map_in_parallel(config.nb_threads, batch, |(obj, street)| {
+            let mut converted = Converted::default();
+            convert_obj(obj, config, boundaries, street.as_deref(), &mut converted);
+            converted
+        });
+        self.conversion.add(count_objs, start.elapsed());
+
+        let start = Instant::now();
+        let count_addresses = converted.iter().map(|c| c.addresses.len()).sum();
+
+        for converted in converted {
+            self.insert_converted(converted);
+        }
+
+        self.insertion.add(count_addresses, start.elapsed());
+    }
+
+    /// Insert the addresses built from some objects.
+    fn insert_converted(&mut self, converted: Converted) {
+        self.count_outside += converted.count_outside;
+        self.count_split += converted.count_split;
+        self.count_split_addresses += converted.count_split_addresses;
+
+        for (address, footprint) in converted.addresses {
+            self.insert(address, footprint.as_ref());
+        }
+    }
+
+    /// Handle a way selected as a street, unless it was already imported.
+    fn handle_street(&mut self, obj: DepObj) {
+        if let Some(imported) = &mut self.imported {
+            // Streets are told apart from the addresses of the same way by a street hash which
+            // can't be the one of a name
+            if !imported.insert((obj.root.id(), u64::MAX)) {
+                return;
+            }
+        }
+
+        let Some(street) = new_street(&obj, self.boundaries.as_ref()) else {
+            return;
+        };
+
+        if let Some(area) = &self.config.area {
+            if !area.contains(street.lat, street.lon) {
+                return;
+            }
+        }
+
+        self.count_streets += 1;
+        self.db.insert_street(street);
+    }
+
+    /// Insert an address, unless it is dropped as it contains (or is contained in) another one.
+    fn insert(&mut self, address: Address, footprint: Option<&Polygon<f64>>) {
+        match &mut self.containment {
+            Some(containment) => containment.insert(self.db, address, footprint),
+            None => self.db.insert(address),
+        }
+    }
+}
+
+/// Convert an object selected by the import into addresses and insert them right away.
+fn handle_obj<T: CompatibleDB>(obj: DepObj, ctx: &mut Context<T>, override_street: Option<&str>) {
+    let mut converted = Converted::default();
+    convert_obj(
+        obj,
+        ctx.config,
+        ctx.boundaries.as_ref(),
+        override_street,
+        &mut converted,
+    );
+    ctx.insert_converted(converted);
+}
+
+/// Addresses built from objects, waiting to be inserted.
+#[derive(Default)]
+struct Converted {
+    /// Addresses, with the footprint of the building they were read from
+    addresses: Vec<(Address, Option<Polygon<f64>>)>,
+    /// Counters of [`Context`]
+    count_outside: u64,
+    count_split: u64,
+    count_split_addresses: u64,
+}
+
+/// Build the addresses of an object selected by the import, following the rules explained at the
+/// crate level. This doesn't depend on other objects, so that objects can be converted in
+/// parallel.
+fn convert_obj(
+    obj: DepObj,
+    config: &ImportConfig,
+    boundaries: Option<&Boundaries>,
+    override_street: Option<&str>,
+    out: &mut Converted,
+) {
+    let (id, info) = (obj.root.id(), obj.info);
+    let mut footprint = None;
     let mut address = {
         match obj.root {
             OsmObj::Node(n) => {
                 let (lat, lon) = (n.lat(), n.lon());
-                new_address(n.tags, lat, lon)
+                new_address(&n.tags, info, lat, lon, config)
             }
             OsmObj::Way(way) => {
                 if let Some((lat, lon)) = get_way_lat_lon(&obj.children) {
-                    new_address(way.tags, lat, lon)
+                    let mut address = new_address(&way.tags, info, lat, lon, config);
+
+                    if address.meta.building.is_some() {
+                        footprint = get_way_footprint(&obj.children);
+                        address.meta.footprint_area = footprint
+                            .as_ref()
+                            .map(|footprint| footprint.chamberlain_duquette_unsigned_area());
+                    }
+
+                    address
                 } else {
                     return;
                 }
@@ -396,7 +823,7 @@ fn handle_obj<T: CompatibleDB>(obj: DepObj, db: &mut T, override_street: Option<
             OsmObj::Relation(r) => {
                 if let Some(addr_name) = r.tags.iter().find(|t| t.0 == "name").map(|(_, n)| n) {
                     for sub_obj in obj.children {
-                        handle_obj(sub_obj, db, Some(addr_name));
+                        convert_obj(sub_obj, config, boundaries, Some(addr_name), out);
                     }
                 }
 
@@ -405,16 +832,49 @@ fn handle_obj<T: CompatibleDB>(obj: DepObj, db: &mut T, override_street: Option<
         }
     };
 
+    if let Some(area) = &config.area {
+        if !area.contains(address.lat, address.lon) {
+            out.count_outside += 1;
+            return;
+        }
+    }
+
     if let Some(street) = override_street {
         address.street = Some(street.into());
     }
 
-    db.insert(address);
+    address.source_id = Some(source_id(id));
+    address.meta.object_kind = Some(object_kind(id).into());
+
+    if let Some(boundaries) = boundaries {
+        boundaries.enrich(&mut address);
+    }
+
+    let numbers = match &address.number {
+        Some(number) => split_housenumber(number, config.expand_ranges),
+        None => Vec::new(),
+    };
+
+    if numbers.len() > 1 {
+        out.count_split += 1;
+
+        for number in numbers.into_iter().filter(|n| is_valid_housenumber(n)) {
+            out.count_split_addresses += 1;
+            let address = Address {
+                number: Some(number),
+                ..address.clone()
+            };
+            out.addresses.push((address, footprint.clone()));
+        }
+    } else {
+        out.addresses.push((address, footprint));
+    }
 }
 
 /// The entry point of the **OpenStreetMap** importer.
 ///
 /// * The `pbf_file` argument is the location the file containing all the **OpenStreetMap** data.
+///   Files with the `.osm` or `.osm.bz2` extension are read as **OSM XML**, others as **PBF**.
 /// * The `db` argument is the mutable database wrapper implementing the `CompatibleDB` trait where
 ///   the data will be stored.
 ///
@@ -428,38 +888,180 @@ fn handle_obj<T: CompatibleDB>(obj: DepObj, db: &mut T, override_street: Option<
 /// import_addresses("some_file.pbf".as_ref(), &mut db);
 /// ```
 pub fn import_addresses<T: CompatibleDB>(pbf_file: &Path, db: &mut T) {
+    import_addresses_with_config(pbf_file, db, &ImportConfig::default())
+}
+
+/// Same as [`import_addresses`], with extra options given by `config`.
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::DB;
+/// use osm::{import_addresses_with_config, ImportConfig};
+///
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// let config = ImportConfig {
+///     boundaries: true,
+///     ..ImportConfig::default()
+/// };
+/// import_addresses_with_config("some_file.pbf".as_ref(), &mut db, &config);
+/// ```
+pub fn import_addresses_with_config<T: CompatibleDB>(
+    pbf_file: &Path,
+    db: &mut T,
+    config: &ImportConfig,
+) {
+    import_file(pbf_file, db, config, None)
+}
+
+/// Same as [`import_addresses_with_config`] for several input files, such as regional extracts
+/// whose borders overlap: objects found in several files are only imported from the first one.
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::DB;
+/// use osm::{import_extracts, ImportConfig};
+///
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+/// let extracts = ["ile-de-france.pbf", "centre.pbf"];
+/// import_extracts(&extracts, &mut db, &ImportConfig::default());
+/// ```
+pub fn import_extracts<T: CompatibleDB>(
+    files: &[impl AsRef<Path>],
+    db: &mut T,
+    config: &ImportConfig,
+) {
+    let mut imported = FxHashSet::default();
+
+    for file in files {
+        import_file(file.as_ref(), db, config, Some(&mut imported));
+    }
+}
+
+/// Import the addresses of a file, skipping the objects which are already in `imported`. See
+/// [`Context::imported`].
+fn import_file<T: CompatibleDB>(
+    pbf_file: &Path,
+    db: &mut T,
+    config: &ImportConfig,
+    imported: Option<&mut FxHashSet<(OsmId, u64)>>,
+) {
     let count_before = db.get_nb_addresses();
 
-    let filter_obj = |obj: &OsmObj| match obj {
-        OsmObj::Node(n) => {
-            n.tags.iter().any(is_valid_housenumber_tag)
-                && n.tags.iter().any(|x| x.0 == "addr:street")
-        }
-        OsmObj::Way(w) => {
-            !w.nodes.is_empty()
-                && w.tags.iter().any(is_valid_housenumber_tag)
-                && w.tags.iter().any(|x| x.0 == "addr:street")
-        }
-        OsmObj::Relation(r) => {
-            !r.refs.is_empty()
-                && r.tags
-                    .iter()
-                    .any(|x| x.0 == "type" && x.1 == "associatedStreet")
-                && r.tags.iter().any(|x| x.0 == "name")
-        }
+    // Init reader
+    let mut reader = open_input(pbf_file);
+
+    let boundaries = config.boundaries.then(|| {
+        let mut builder = BoundariesBuilder::default();
+        // Only this pass keeps the tags describing boundaries
+        fetch_objects(
+            3,
+            config.nb_threads,
+            reader.as_mut(),
+            BOUNDARY_TAGS_TO_KEEP,
+            is_boundary,
+            |obj| builder.add(&obj),
+        );
+        tprintln!("[OSM] Extracted {} boundaries", builder.len());
+        builder.build()
+    });
+
+    let mut ctx = Context {
+        db,
+        boundaries,
+        config,
+        count_outside: 0,
+        count_split: 0,
+        count_split_addresses: 0,
+        containment: Containment::new(config.contained_addresses),
+        imported,
+        count_already_imported: 0,
+        count_streets: 0,
+        batch: Vec::new(),
+        conversion: Stage::default(),
+        insertion: Stage::default(),
     };
 
-    // Init reader
-    let file = BufReader::with_capacity(
-        PBF_BUFFER_SIZE,
-        File::open(&pbf_file)
-            .unwrap_or_else(|err| panic!("Failed to open file {:?}: {}", pbf_file, err)),
+    let filter_obj = |obj: &OsmObj| is_address(obj, config);
+
+    if let Some(store_config) = &config.node_store {
+        fetch_objects_with_store(
+            store_config,
+            config.nb_threads,
+            reader.as_mut(),
+            filter_obj,
+            |obj, street| ctx.handle_new_obj(obj, street),
+        );
+    } else {
+        fetch_objects(
+            3,
+            config.nb_threads,
+            reader.as_mut(),
+            &[],
+            filter_obj,
+            |obj| ctx.handle_new_obj(obj, None),
+        );
+    }
+
+    ctx.flush();
+    tprintln!(
+        "[OSM] Converted objects: {}, inserted addresses: {}",
+        ctx.conversion,
+        ctx.insertion
     );
 
-    let mut reader = OsmPbfReader::new(file);
-    fetch_objects(3, &mut reader, filter_obj, db);
+    if config.streets {
+        if let Some(store_config) = &config.node_store {
+            fetch_objects_with_store(
+                store_config,
+                config.nb_threads,
+                reader.as_mut(),
+                is_street,
+                |obj, _| ctx.handle_street(obj),
+            );
+        } else {
+            fetch_objects(
+                3,
+                config.nb_threads,
+                reader.as_mut(),
+                &[],
+                is_street,
+                |obj| ctx.handle_street(obj),
+            );
+        }
+
+        tprintln!("[OSM] Extracted {} streets", ctx.count_streets);
+    }
 
-    let count_after = db.get_nb_addresses();
+    if ctx.imported.is_some() {
+        tprintln!(
+            "[OSM] Ignored {} objects already imported from another file",
+            ctx.count_already_imported
+        );
+    }
+
+    if config.area.is_some() {
+        tprintln!(
+            "[OSM] Ignored {} addresses outside of the area",
+            ctx.count_outside
+        );
+    }
+
+    if let Some(containment) = &ctx.containment {
+        tprintln!(
+            "[OSM] Ignored {} addresses contained in a building with the same address",
+            containment.count_dropped()
+        );
+    }
+
+    tprintln!(
+        "[OSM] Split {} multi-valued housenumbers into {} addresses",
+        ctx.count_split,
+        ctx.count_split_addresses
+    );
+
+    let count_after = ctx.db.get_nb_addresses();
     tprintln!(
         "[OSM] Added {} addresses (total: {})",
         count_after - count_before,
@@ -467,11 +1069,58 @@ pub fn import_addresses<T: CompatibleDB>(pbf_file: &Path, db: &mut T) {
     );
 }
 
-fn is_valid_housenumber_tag(tag_kv: (&String, &String)) -> bool {
+/// Check if an object must be imported, following the rules explained at the crate level. The
+/// housenumber and street of nodes and ways are read using the tag schemes of `config`.
+fn is_address(obj: &OsmObj, config: &ImportConfig) -> bool {
+    let has_address = |tags: &Tags| {
+        config
+            .tag_schemes
+            .number_and_street(tags, config.country.as_deref())
+            .is_some_and(|(number, _)| {
+                // Multi-valued housenumbers are split later on, each value is checked separately
+                number
+                    .split(';')
+                    .any(|n| !n.trim().is_empty() && is_valid_housenumber(n.trim()))
+            })
+    };
+
+    match obj {
+        OsmObj::Node(n) => has_address(&n.tags),
+        OsmObj::Way(w) => !w.nodes.is_empty() && has_address(&w.tags),
+        OsmObj::Relation(r) => {
+            !r.refs.is_empty()
+                && r.tags
+                    .iter()
+                    .any(|x| x.0 == "type" && x.1 == "associatedStreet")
+                && r.tags.iter().any(|x| x.0 == "name")
+        }
+    }
+}
+
+/// Identifier of an object, stored in the `source_id` of its addresses. It has the same form as
+/// in the URLs of the OpenStreetMap website, such as `way/123`.
+fn source_id(id: OsmId) -> String {
+    match id {
+        OsmId::Node(id) => format!("node/{}", id.0),
+        OsmId::Way(id) => format!("way/{}", id.0),
+        OsmId::Relation(id) => format!("relation/{}", id.0),
+    }
+    .into()
+}
+
+/// Kind of an object, stored in the metadata of its addresses.
+fn object_kind(id: OsmId) -> &'static str {
+    match id {
+        OsmId::Node(_) => "node",
+        OsmId::Way(_) => "way",
+        OsmId::Relation(_) => "relation",
+    }
+}
+
+fn is_valid_housenumber(number: &str) -> bool {
     // Long "housenumber" values should be excluded as they probably don't represent a house number.
     // Example: "addr:housenumber=Cochin International Airport Limited"
-    let (key, value) = tag_kv;
-    key == "addr:housenumber" && value.len() <= MAX_VALID_HOUSENUMBER_LENGTH
+    number.len() <= MAX_VALID_HOUSENUMBER_LENGTH
 }
 
 #[cfg(test)]
@@ -493,4 +1142,153 @@ mod tests {
 
         let _ = std::fs::remove_file(db_file); // we ignore any potential error
     }
+
+    #[test]
+    fn check_xml_input() {
+        let db_file = "check_xml_input.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+        let osm_file = "test-files/osm_input.osm";
+        import_addresses(osm_file.as_ref(), &mut db);
+        assert_eq!(db.get_nb_addresses(), 361);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_node_store() {
+        for kind in [NodeStoreKind::Dense, NodeStoreKind::Sparse] {
+            let db_file = "check_node_store.db";
+            let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+            let config = ImportConfig {
+                node_store: Some(NodeStoreConfig {
+                    path: "check_node_store.bin".into(),
+                    kind,
+                }),
+                ..ImportConfig::default()
+            };
+
+            let pbf_file = "test-files/osm_input.pbf";
+            import_addresses_with_config(pbf_file.as_ref(), &mut db, &config);
+            assert_eq!(db.get_nb_addresses(), 361, "{:?}", kind);
+
+            let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        }
+
+        let _ = std::fs::remove_file("check_node_store.bin");
+    }
+
+    #[test]
+    fn check_nb_threads() {
+        let all = [-180., -90., 180., 90.];
+        let mut imported = Vec::new();
+
+        for nb_threads in [Some(1), Some(4)] {
+            let db_file = "check_nb_threads.db";
+            let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+            let config = ImportConfig {
+                nb_threads,
+                ..ImportConfig::default()
+            };
+
+            let pbf_file = "test-files/osm_input.pbf";
+            import_addresses_with_config(pbf_file.as_ref(), &mut db, &config);
+            imported.push(db.get_addresses_in_bbox(all));
+
+            let _ = std::fs::remove_file(db_file); // we ignore any potential error
+        }
+
+        assert_eq!(imported[0].len(), 361);
+        assert_eq!(imported[0], imported[1]);
+    }
+
+    #[test]
+    fn check_area() {
+        let db_file = "check_area.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+        let config = ImportConfig {
+            area: Some("2.0503,48.8503,2.0527,48.8521".parse().unwrap()),
+            ..ImportConfig::default()
+        };
+
+        let osm_file = "test-files/osm_input.osm";
+        import_addresses_with_config(osm_file.as_ref(), &mut db, &config);
+        assert_eq!(db.get_nb_addresses(), 15);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_streets() {
+        let db_file = "check_streets.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+        let config = ImportConfig {
+            streets: true,
+            ..ImportConfig::default()
+        };
+
+        let osm_file = "test-files/osm_input.osm";
+        import_extracts(&[osm_file, osm_file], &mut db, &config);
+        assert_eq!(db.get_nb_addresses(), 361);
+        assert_eq!(db.get_nb_streets(), 65);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_extracts() {
+        let db_file = "check_extracts.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+        let osm_file = "test-files/osm_input.osm";
+        import_extracts(&[osm_file, osm_file], &mut db, &ImportConfig::default());
+        assert_eq!(db.get_nb_addresses(), 361);
+        assert_eq!(db.get_nb_errors(), 0);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+
+    #[test]
+    fn check_changes() {
+        let db_file = "check_changes.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+        let osm_file = "test-files/osm_input.osm";
+        import_addresses(osm_file.as_ref(), &mut db);
+        let previous = db.get_address(13, "Rue Georges Buffon");
+        assert_eq!(previous.len(), 1);
+        assert_eq!(previous[0].source_id.as_deref(), Some("way/103917893"));
+        assert_eq!(previous[0].meta.object_kind.as_deref(), Some("way"));
+        assert_eq!(previous[0].meta.building.as_deref(), Some("yes"));
+        assert!(previous[0].meta.footprint_area.unwrap() > 10.);
+        assert_eq!(previous[0].meta.version, Some(1));
+
+        apply_changes(
+            "test-files/osm_changes.osc".as_ref(),
+            &mut db,
+            &ImportConfig::default(),
+        );
+        assert_eq!(db.get_nb_addresses(), 361);
+
+        // created node
+        assert_eq!(db.get_address(42, "Rue Louis Le Vau").len(), 1);
+        // modified node
+        assert_eq!(db.get_address(3, "Rue Louis Le Vau").len(), 0);
+        assert_eq!(db.get_address(33, "Rue Louis Le Vau").len(), 1);
+        // modified way, which keeps its location as its nodes are not part of the change
+        let modified = db.get_address(113, "Rue Georges Buffon");
+        assert_eq!(modified.len(), 1);
+        assert!((modified[0].lat - previous[0].lat).abs() < 1e-6);
+        assert!((modified[0].lon - previous[0].lon).abs() < 1e-6);
+        assert_eq!(modified[0].meta.version, Some(2));
+        assert_eq!(modified[0].meta.timestamp, Some(1_609_459_200));
+        // deleted way
+        assert_eq!(db.get_address(16, "Rue Jean-Baptiste Tubi").len(), 0);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
 }
@@ -1,14 +1,121 @@
-use std::env;
+use std::path::PathBuf;
+
+use structopt::StructOpt;
 use tools::{self, teprintln, tprintln, CompatibleDB, DB};
 
+#[derive(Debug, StructOpt)]
+#[structopt(
+    name = "osm",
+    about = "Import addresses from an OpenStreetMap PBF or XML file."
+)]
+struct Params {
+    /// Path to the PBF file, or to an OSM XML file (`.osm` or `.osm.bz2`). Several files, such as
+    /// overlapping extracts, can be given: objects found in several of them are imported once.
+    /// With `--apply-changes`, paths to OsmChange files (`.osc`, `.osc.gz` or `.osc.bz2`) applied
+    /// in the given order.
+    #[structopt(required = true)]
+    input: Vec<PathBuf>,
+
+    /// Apply the OsmChange files given as input to the existing output database, instead of
+    /// importing addresses into a new one.
+    #[structopt(long)]
+    apply_changes: bool,
+
+    /// Path of the output database.
+    #[structopt(long, default_value = "addresses.db")]
+    output_db: String,
+
+    /// Fill missing city, district, region and postcode using the administrative and postal
+    /// code boundaries of the input file.
+    #[structopt(long)]
+    boundaries: bool,
+
+    /// Store node locations in this file instead of keeping pending objects in memory, this
+    /// reduces memory usage for large input files.
+    #[structopt(long)]
+    node_store: Option<PathBuf>,
+
+    /// Layout of the node store: `dense` for large files (such as a planet file), `sparse`
+    /// otherwise.
+    #[structopt(long, default_value = "sparse")]
+    node_store_kind: osm::NodeStoreKind,
+
+    /// Only import addresses inside of this bounding box, given as
+    /// `min_lon,min_lat,max_lon,max_lat`.
+    #[structopt(long, conflicts_with = "polygon")]
+    bbox: Option<osm::Area>,
+
+    /// Only import addresses inside of the polygons of this GeoJSON file.
+    #[structopt(long)]
+    polygon: Option<PathBuf>,
+
+    /// Country code (such as `CZ`) used to select the tag scheme of objects without an
+    /// `addr:country` tag.
+    #[structopt(long)]
+    country: Option<String>,
+
+    /// JSON file overriding the built-in tag schemes, which map OSM tags to address fields.
+    #[structopt(long)]
+    tag_schemes: Option<PathBuf>,
+
+    /// Expand numeric ranges of housenumbers, such as `10-14`, into separate addresses.
+    #[structopt(long)]
+    expand_ranges: bool,
+
+    /// What to do with address nodes inside of a building with the same address: `keep-both`,
+    /// `prefer-node` or `prefer-building`.
+    #[structopt(long, default_value = "keep-both")]
+    contained_addresses: osm::ContainedAddresses,
+
+    /// Also extract named streets into the `streets` table, whether or not they have addresses.
+    #[structopt(long)]
+    streets: bool,
+
+    /// Number of threads objects are filtered, resolved and converted with, all the available
+    /// cores are used by default.
+    #[structopt(long)]
+    num_threads: Option<usize>,
+}
+
 fn main() {
-    let args = env::args().collect::<Vec<String>>();
-    if args.len() < 2 {
-        eprintln!("Expected PBF file path");
-        return;
+    let params = Params::from_args();
+    let area = params.bbox.or_else(|| {
+        params
+            .polygon
+            .map(|path| osm::Area::from_geojson_file(&path).unwrap_or_else(|err| panic!("{}", err)))
+    });
+
+    let tag_schemes = params
+        .tag_schemes
+        .map(|path| osm::TagSchemes::from_file(&path).unwrap_or_else(|err| panic!("{}", err)))
+        .unwrap_or_default();
+
+    let config = osm::ImportConfig {
+        boundaries: params.boundaries,
+        node_store: params.node_store.map(|path| osm::NodeStoreConfig {
+            path,
+            kind: params.node_store_kind,
+        }),
+        area,
+        tag_schemes,
+        country: params.country,
+        expand_ranges: params.expand_ranges,
+        contained_addresses: params.contained_addresses,
+        streets: params.streets,
+        nb_threads: params.num_threads,
+    };
+
+    let mut db =
+        DB::new(&params.output_db, 1000, !params.apply_changes).expect("Failed to create DB");
+
+    if params.apply_changes {
+        for input in &params.input {
+            osm::apply_changes(input, &mut db, &config);
+        }
+    } else {
+        osm::import_extracts(&params.input, &mut db, &config);
     }
-    let mut db = DB::new("addresses.db", 1000, true).expect("Failed to create DB");
-    osm::import_addresses(args[1].as_ref(), &mut db);
+
     tprintln!(
         "Got {} addresses in {} cities (and {} errors)",
         db.get_nb_addresses(),
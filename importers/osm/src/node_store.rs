@@ -0,0 +1,337 @@
+//! On-disk storage of node locations, used to resolve ways without keeping pending objects in
+//! memory.
+//!
+//! Two layouts are available:
+//!  * **dense**: a memory-mapped array indexed by node ID, each slot holding the location of the
+//!    node. The file is as large as the greatest node ID times 8 bytes, but most of it is usually
+//!    never written and stays sparse on disk. This is the best choice for a planet file.
+//!  * **sparse**: a list of `(id, location)` entries sorted by ID, looked up using a binary
+//!    search. This is the best choice for extracts, where only a small part of the IDs are used.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use memmap2::{Mmap, MmapMut};
+use osmpbfreader::objects::NodeId;
+
+/// Size of the write buffer put on top of the sparse store file.
+const WRITE_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+
+/// Size of a slot of the dense store: latitude and longitude.
+const DENSE_SLOT_SIZE: usize = 8;
+
+/// Size of an entry of the sparse store: ID, latitude and longitude.
+const SPARSE_ENTRY_SIZE: usize = 16;
+
+/// Number of slots the dense store is grown by, at least.
+const DENSE_MIN_GROWTH: u64 = 16 * 1024 * 1024;
+
+/// Locations are stored with an offset so that a zero-filled slot can't be a valid location.
+const LAT_OFFSET: i64 = 1_000_000_000;
+const LON_OFFSET: i64 = 2_000_000_000;
+
+/// Layout of the node store, see the module documentation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeStoreKind {
+    Dense,
+    Sparse,
+}
+
+impl FromStr for NodeStoreKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "dense" => Ok(Self::Dense),
+            "sparse" => Ok(Self::Sparse),
+            _ => Err(format!(
+                "unknown node store kind `{}`, expected `dense` or `sparse`",
+                s
+            )),
+        }
+    }
+}
+
+/// Where and how node locations are stored during an import.
+#[derive(Clone, Debug)]
+pub struct NodeStoreConfig {
+    /// File used to store locations, it is removed at the end of the import.
+    pub path: PathBuf,
+    pub kind: NodeStoreKind,
+}
+
+/// Encode a location, in decimicro degrees, as stored in the dense store.
+fn encode_location(lat: i32, lon: i32) -> [u8; DENSE_SLOT_SIZE] {
+    let lat = (i64::from(lat) + LAT_OFFSET) as u32;
+    let lon = (i64::from(lon) + LON_OFFSET) as u32;
+    let mut buf = [0; DENSE_SLOT_SIZE];
+    buf[..4].copy_from_slice(&lat.to_le_bytes());
+    buf[4..].copy_from_slice(&lon.to_le_bytes());
+    buf
+}
+
+/// Decode a location written by `encode_location`, returns `None` for an empty slot.
+fn decode_location(buf: &[u8]) -> Option<(i32, i32)> {
+    let lat = u32::from_le_bytes(buf[..4].try_into().unwrap());
+    let lon = u32::from_le_bytes(buf[4..8].try_into().unwrap());
+
+    if lat == 0 {
+        return None;
+    }
+
+    Some((
+        (i64::from(lat) - LAT_OFFSET) as i32,
+        (i64::from(lon) - LON_OFFSET) as i32,
+    ))
+}
+
+/// Memory-mapped array of locations indexed by node ID.
+struct DenseStore {
+    file: File,
+    map: MmapMut,
+    nb_slots: u64,
+}
+
+impl DenseStore {
+    fn create(path: &Path) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(DENSE_MIN_GROWTH * DENSE_SLOT_SIZE as u64)?;
+        let map = unsafe { MmapMut::map_mut(&file)? };
+
+        Ok(Self {
+            file,
+            map,
+            nb_slots: DENSE_MIN_GROWTH,
+        })
+    }
+
+    fn insert(&mut self, id: u64, lat: i32, lon: i32) -> io::Result<()> {
+        if id >= self.nb_slots {
+            self.map.flush()?;
+            self.nb_slots = (id + 1).max(self.nb_slots * 2);
+            self.file.set_len(self.nb_slots * DENSE_SLOT_SIZE as u64)?;
+            self.map = unsafe { MmapMut::map_mut(&self.file)? };
+        }
+
+        let start = id as usize * DENSE_SLOT_SIZE;
+        self.map[start..start + DENSE_SLOT_SIZE].copy_from_slice(&encode_location(lat, lon));
+        Ok(())
+    }
+
+    fn get(&self, id: u64) -> Option<(i32, i32)> {
+        if id >= self.nb_slots {
+            return None;
+        }
+
+        let start = id as usize * DENSE_SLOT_SIZE;
+        decode_location(&self.map[start..start + DENSE_SLOT_SIZE])
+    }
+}
+
+/// List of `(id, location)` entries, written sequentially then memory-mapped and looked up by
+/// binary search once complete.
+struct SparseStore {
+    path: PathBuf,
+    writer: Option<BufWriter<File>>,
+    map: Option<Mmap>,
+    last_id: i64,
+    sorted: bool,
+}
+
+impl SparseStore {
+    fn create(path: &Path) -> io::Result<Self> {
+        Ok(Self {
+            path: path.to_path_buf(),
+            writer: Some(BufWriter::with_capacity(
+                WRITE_BUFFER_SIZE,
+                File::create(path)?,
+            )),
+            map: None,
+            last_id: i64::MIN,
+            sorted: true,
+        })
+    }
+
+    fn insert(&mut self, id: i64, lat: i32, lon: i32) -> io::Result<()> {
+        let writer = self
+            .writer
+            .as_mut()
+            .expect("can't insert in a finished node store");
+
+        self.sorted &= id > self.last_id;
+        self.last_id = id;
+        writer.write_all(&id.to_le_bytes())?;
+        writer.write_all(&encode_location(lat, lon))
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        let writer = match self.writer.take() {
+            Some(writer) => writer,
+            None => return Ok(()),
+        };
+
+        let file = writer.into_inner().map_err(|err| err.into_error())?;
+
+        if !self.sorted {
+            // Nodes are sorted by ID in PBF files, this should only happen for small files.
+            let mut entries: Vec<_> = fs::read(&self.path)?
+                .chunks_exact(SPARSE_ENTRY_SIZE)
+                .map(|entry| <[u8; SPARSE_ENTRY_SIZE]>::try_from(entry).unwrap())
+                .collect();
+            entries.sort_by_key(|entry| i64::from_le_bytes(entry[..8].try_into().unwrap()));
+            fs::write(&self.path, entries.concat())?;
+        }
+
+        if file.metadata()?.len() > 0 {
+            self.map = Some(unsafe { Mmap::map(&File::open(&self.path)?)? });
+        }
+
+        Ok(())
+    }
+
+    fn get(&self, id: i64) -> Option<(i32, i32)> {
+        let map = self.map.as_ref()?;
+        let entry_id = |index: usize| {
+            let start = index * SPARSE_ENTRY_SIZE;
+            i64::from_le_bytes(map[start..start + 8].try_into().unwrap())
+        };
+
+        let (mut low, mut high) = (0, map.len() / SPARSE_ENTRY_SIZE);
+
+        while low < high {
+            let mid = (low + high) / 2;
+
+            match entry_id(mid).cmp(&id) {
+                std::cmp::Ordering::Less => low = mid + 1,
+                std::cmp::Ordering::Greater => high = mid,
+                std::cmp::Ordering::Equal => {
+                    let start = mid * SPARSE_ENTRY_SIZE + 8;
+                    return decode_location(&map[start..start + DENSE_SLOT_SIZE]);
+                }
+            }
+        }
+
+        None
+    }
+}
+
+enum Store {
+    Dense(DenseStore),
+    Sparse(SparseStore),
+}
+
+/// Storage of node locations, backed by a file which is removed when this is dropped.
+pub(crate) struct NodeStore {
+    path: PathBuf,
+    store: Store,
+    count: u64,
+}
+
+impl NodeStore {
+    /// Create an empty store, any existing file at the configured path is overwritten.
+    pub fn create(config: &NodeStoreConfig) -> io::Result<Self> {
+        let store = match config.kind {
+            NodeStoreKind::Dense => Store::Dense(DenseStore::create(&config.path)?),
+            NodeStoreKind::Sparse => Store::Sparse(SparseStore::create(&config.path)?),
+        };
+
+        Ok(Self {
+            path: config.path.clone(),
+            store,
+            count: 0,
+        })
+    }
+
+    /// Store the location of a node, given in decimicro degrees.
+    ///
+    /// Negative IDs can't be stored in a dense store, they are ignored.
+    pub fn insert(&mut self, id: NodeId, lat: i32, lon: i32) -> io::Result<()> {
+        match &mut self.store {
+            Store::Dense(store) => match u64::try_from(id.0) {
+                Ok(id) => store.insert(id, lat, lon)?,
+                Err(_) => return Ok(()),
+            },
+            Store::Sparse(store) => store.insert(id.0, lat, lon)?,
+        }
+
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Must be called once all nodes have been inserted, before any call to `get`.
+    pub fn finish(&mut self) -> io::Result<()> {
+        match &mut self.store {
+            Store::Dense(store) => store.map.flush(),
+            Store::Sparse(store) => store.finish(),
+        }
+    }
+
+    /// Returns the location of a node in decimicro degrees.
+    pub fn get(&self, id: NodeId) -> Option<(i32, i32)> {
+        match &self.store {
+            Store::Dense(store) => store.get(u64::try_from(id.0).ok()?),
+            Store::Sparse(store) => store.get(id.0),
+        }
+    }
+
+    /// Returns the number of nodes inserted in the store.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+}
+
+impl Drop for NodeStore {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path); // we ignore any potential error
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_store(kind: NodeStoreKind, path: &str) {
+        let config = NodeStoreConfig {
+            path: path.into(),
+            kind,
+        };
+
+        let mut store = NodeStore::create(&config).expect("failed to create store");
+        store.insert(NodeId(3), 488_500_000, 20_500_000).unwrap();
+        store.insert(NodeId(1), 0, 0).unwrap();
+        store
+            .insert(NodeId(50_000_000), -900_000_000, -1_800_000_000)
+            .unwrap();
+        store.finish().unwrap();
+
+        assert_eq!(store.len(), 3);
+        assert_eq!(store.get(NodeId(3)), Some((488_500_000, 20_500_000)));
+        assert_eq!(store.get(NodeId(1)), Some((0, 0)));
+        assert_eq!(
+            store.get(NodeId(50_000_000)),
+            Some((-900_000_000, -1_800_000_000))
+        );
+        assert_eq!(store.get(NodeId(2)), None);
+        assert_eq!(store.get(NodeId(60_000_000)), None);
+
+        drop(store);
+        assert!(!Path::new(path).exists());
+    }
+
+    #[test]
+    fn check_dense_store() {
+        check_store(NodeStoreKind::Dense, "check_dense_store.bin");
+    }
+
+    #[test]
+    fn check_sparse_store() {
+        check_store(NodeStoreKind::Sparse, "check_sparse_store.bin");
+    }
+}
@@ -0,0 +1,103 @@
+//! Helpers to spread the stages of the import over all the available cores, and to report their
+//! throughput.
+//!
+//! PBF blocks are decoded in parallel by the readers. Objects kept in memory are then filtered and
+//! stripped of their unused tags by batches spread over all cores, only the graph of their
+//! dependencies is built on a single thread as it follows the order of the file. Objects are
+//! converted into addresses by batches spread over all cores, and locations of nodes are read from
+//! the node store by batches as well. Batches have a bounded size, so that the memory usage doesn't
+//! depend on the size of the input.
+
+use std::fmt;
+use std::num::NonZeroUsize;
+use std::thread;
+use std::time::Duration;
+
+/// Apply `f` to all `items` using `nb_threads` threads, or all the available cores when it isn't
+/// set. The order of items is preserved.
+///
+/// Threads are spawned for each call, so `items` should be large enough for this cost to be
+/// negligible.
+pub(crate) fn map_in_parallel<T: Send, U: Send>(
+    nb_threads: Option<usize>,
+    items: Vec<T>,
+    f: impl Fn(T) -> U + Sync,
+) -> Vec<U> {
+    let nb_threads = nb_threads
+        .unwrap_or_else(|| thread::available_parallelism().map_or(1, NonZeroUsize::get))
+        .max(1);
+
+    if nb_threads == 1 || items.len() < 2 {
+        return items.into_iter().map(f).collect();
+    }
+
+    let chunk_size = items.len().div_ceil(nb_threads);
+    let mut items = items.into_iter();
+    let chunks: Vec<Vec<T>> = (0..nb_threads)
+        .map(|_| items.by_ref().take(chunk_size).collect())
+        .collect();
+
+    let f = &f;
+
+    thread::scope(|scope| {
+        let workers: Vec<_> = chunks
+            .into_iter()
+            .map(|chunk| scope.spawn(move || chunk.into_iter().map(f).collect::<Vec<_>>()))
+            .collect();
+
+        workers
+            .into_iter()
+            .flat_map(|worker| worker.join().expect("worker thread panicked"))
+            .collect()
+    })
+}
+
+/// Number of items processed by a stage of the import, and the time spent in it.
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct Stage {
+    count: u64,
+    elapsed: Duration,
+}
+
+impl Stage {
+    pub(crate) fn add(&mut self, count: usize, elapsed: Duration) {
+        self.count += count as u64;
+        self.elapsed += elapsed;
+    }
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.elapsed.as_secs_f64();
+        let rate = if secs > 0. {
+            self.count as f64 / secs
+        } else {
+            0.
+        };
+
+        write!(f, "{} in {:.1}s ({:.0}/s)", self.count, secs, rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_map_in_parallel() {
+        for nb_threads in [None, Some(1), Some(3)] {
+            let items: Vec<u64> = (0..1000).collect();
+            let doubled = map_in_parallel(nb_threads, items, |x| 2 * x);
+            assert_eq!(doubled, (0..1000).map(|x| 2 * x).collect::<Vec<_>>());
+            assert!(map_in_parallel(nb_threads, Vec::<u64>::new(), |x| x).is_empty());
+        }
+    }
+
+    #[test]
+    fn check_stage() {
+        let mut stage = Stage::default();
+        stage.add(300, Duration::from_secs(2));
+        stage.add(100, Duration::from_secs(2));
+        assert_eq!(stage.to_string(), "400 in 4.0s (100/s)");
+    }
+}
@@ -0,0 +1,79 @@
+//! Input files of the importer: **PBF** files and **OSM XML** files, the latter being possibly
+//! compressed (bzip2 is common for OSM XML files, gzip for **OsmChange** files).
+
+use std::fs::File;
+use std::io::{BufRead, BufReader, Seek};
+use std::path::{Path, PathBuf};
+
+use osmpbfreader::{OsmObj, OsmPbfReader};
+use par_map::ParMap;
+use tools::{open_input as open_file, strip_compression_extension};
+
+use crate::info::{read_pbf_blob, Info};
+use crate::xml::OsmXmlReader;
+
+/// Size of the read buffer put on top of the input file
+const READ_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+
+/// A source of OSM objects that can be read several times.
+pub(crate) trait ObjReader {
+    /// Iterate over all the objects of the input with their version and timestamp, starting from
+    /// its beginning.
+    fn objects(&mut self) -> Box<dyn Iterator<Item = (OsmObj, Info)> + '_>;
+}
+
+impl<R: BufRead + Seek + Send> ObjReader for OsmPbfReader<R> {
+    fn objects(&mut self) -> Box<dyn Iterator<Item = (OsmObj, Info)> + '_> {
+        self.rewind().expect("could not rewind PBF reader");
+        Box::new(
+            self.blobs()
+                .par_flat_map(|blob| read_pbf_blob(blob.expect("could not read pbf"))),
+        )
+    }
+}
+
+/// Open an XML file, which is decompressed on the fly if it is compressed with gzip, bzip2 or
+/// zstd.
+pub(crate) fn open_xml(path: &Path) -> OsmXmlReader<Box<dyn BufRead>> {
+    let input =
+        open_file(path).unwrap_or_else(|err| panic!("Failed to open file {:?}: {}", path, err));
+
+    OsmXmlReader::new(input)
+}
+
+/// An OSM XML file, which is opened again every time it is read.
+struct XmlFile {
+    path: PathBuf,
+}
+
+impl ObjReader for XmlFile {
+    fn objects(&mut self) -> Box<dyn Iterator<Item = (OsmObj, Info)> + '_> {
+        let path = &self.path;
+
+        Box::new(open_xml(path).map(move |obj| {
+            obj.unwrap_or_else(|err| panic!("could not read OSM XML {:?}: {}", path, err))
+        }))
+    }
+}
+
+/// Open an input file, its format is guessed from its extension: `.osm` files, which may be
+/// compressed (such as `.osm.bz2`), are read as OSM XML, anything else as PBF.
+pub(crate) fn open_input(path: &Path) -> Box<dyn ObjReader> {
+    let name = strip_compression_extension(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    if name.ends_with(".osm") {
+        return Box::new(XmlFile {
+            path: path.to_path_buf(),
+        });
+    }
+
+    let file = BufReader::with_capacity(
+        READ_BUFFER_SIZE,
+        File::open(path).unwrap_or_else(|err| panic!("Failed to open file {:?}: {}", path, err)),
+    );
+
+    Box::new(OsmPbfReader::new(file))
+}
@@ -0,0 +1,110 @@
+//! Extraction of named streets, whether or not they have addresses.
+//!
+//! Streets are read from `highway=*` ways with a `name` tag. Each way gives a separate street, the
+//! pieces of a same street are not merged, so that the location of a street stays close to the
+//! part of it being looked for.
+
+use geo::{BoundingRect, Centroid, LineString};
+use osmpbfreader::OsmObj;
+
+use tools::Street;
+
+use crate::boundaries::Boundaries;
+use crate::{source_id, DepObj};
+
+/// Check if an object must be imported as a street.
+pub(crate) fn is_street(obj: &OsmObj) -> bool {
+    match obj {
+        OsmObj::Way(way) => {
+            way.nodes.len() >= 2
+                && way.tags.contains_key("highway")
+                && way.tags.get("name").is_some_and(|name| !name.is_empty())
+        }
+        _ => false,
+    }
+}
+
+/// Build a street from a way which validated [`is_street`], together with its nodes. The city is
+/// read from the `addr:city` tag of the way, or from the boundaries containing its centroid.
+pub(crate) fn new_street(obj: &DepObj, boundaries: Option<&Boundaries>) -> Option<Street> {
+    let way = obj.root.way()?;
+    let name = way.tags.get("name")?;
+
+    let line: LineString<f64> = obj
+        .children
+        .iter()
+        .filter_map(|child| child.root.node())
+        .map(|node| (node.lon(), node.lat()))
+        .collect();
+
+    let centroid = line.centroid()?;
+    let bbox = line.bounding_rect()?;
+
+    let city = way
+        .tags
+        .get("addr:city")
+        .map(|city| city.as_str().into())
+        .or_else(|| boundaries?.city(centroid.y(), centroid.x()));
+
+    Some(Street {
+        name: name.as_str().into(),
+        city,
+        lat: centroid.y(),
+        lon: centroid.x(),
+        min_lat: bbox.min().y,
+        min_lon: bbox.min().x,
+        max_lat: bbox.max().y,
+        max_lon: bbox.max().x,
+        source_id: Some(source_id(obj.root.id())),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::info::Info;
+    use osmpbfreader::objects::{Node, NodeId, Tags, Way, WayId};
+
+    fn node(id: i64, lon: f64, lat: f64) -> DepObj {
+        DepObj {
+            root: OsmObj::Node(Node {
+                id: NodeId(id),
+                tags: Tags::new(),
+                decimicro_lat: (lat * 1e7) as i32,
+                decimicro_lon: (lon * 1e7) as i32,
+            }),
+            info: Info::default(),
+            children: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn check_new_street() {
+        let mut tags = Tags::new();
+        tags.insert("highway".into(), "residential".into());
+        tags.insert("name".into(), "Rue Buffon".into());
+
+        let way = OsmObj::Way(Way {
+            id: WayId(1),
+            tags,
+            nodes: vec![NodeId(1), NodeId(2), NodeId(3)],
+        });
+
+        assert!(is_street(&way));
+
+        let obj = DepObj {
+            root: way,
+            info: Info::default(),
+            children: vec![node(1, 0., 0.), node(2, 2., 0.), node(3, 2., 1.)],
+        };
+
+        let street = new_street(&obj, None).unwrap();
+        assert_eq!(street.name, "Rue Buffon");
+        assert_eq!(street.city, None);
+        assert_eq!(street.source_id.as_deref(), Some("way/1"));
+        assert_eq!((street.min_lon, street.max_lon), (0., 2.));
+        assert_eq!((street.min_lat, street.max_lat), (0., 1.));
+        assert!((street.lon - 4. / 3.).abs() < 1e-6);
+        assert!((street.lat - 1. / 6.).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,363 @@
+//! Mapping of OSM tags to the fields of an address, which depends on local conventions.
+//!
+//! Each field is built from a list of templates such as
+//! `"{addr:conscriptionnumber}/{addr:streetnumber}"`: the first template whose tags are all set
+//! on the object is used. A scheme is selected for each object using its `addr:country` tag, or
+//! the default country of the import, and fields which are not specified for a country are built
+//! using the default scheme.
+//!
+//! Built-in schemes can be overridden with a JSON file, mapping country codes (or `default`) to
+//! the templates of the fields to replace:
+//!
+//! ```json
+//! {
+//!     "default": { "district": ["{addr:district}", "{addr:suburb}", "{addr:hamlet}"] },
+//!     "IT": { "unit": ["{addr:unit}", "{addr:door}"] }
+//! }
+//! ```
+//!
+//! Only the `addr:*` and `name` tags are available to templates.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use osmpbfreader::objects::Tags;
+use smartstring::alias::String;
+
+use tools::Address;
+
+/// Templates of each field of a scheme.
+type Fields = &'static [(&'static str, &'static [&'static str])];
+
+/// Templates of the default scheme.
+const DEFAULT_SCHEME: Fields = &[
+    ("number", &["{addr:housenumber}"]),
+    ("street", &["{addr:street}"]),
+    ("unit", &["{addr:unit}"]),
+    ("city", &["{addr:city}"]),
+    ("district", &["{addr:district}", "{addr:suburb}"]),
+    ("region", &["{addr:region}", "{addr:province}"]),
+    ("postcode", &["{addr:postcode}"]),
+];
+
+/// Templates of countries which don't follow the default scheme for some fields.
+const COUNTRY_SCHEMES: &[(&str, Fields)] = &[
+    // Buildings have a conscription number, unique in the municipality, and a street number
+    // when they are on a street. Places without streets only use the conscription number.
+    ("CZ", CONSCRIPTION_SCHEME),
+    ("SK", CONSCRIPTION_SCHEME),
+    // Addresses are based on blocks of a quarter rather than on streets.
+    (
+        "JP",
+        &[
+            (
+                "number",
+                &[
+                    "{addr:block_number}-{addr:housenumber}",
+                    "{addr:housenumber}",
+                ],
+            ),
+            (
+                "street",
+                &[
+                    "{addr:street}",
+                    "{addr:quarter}{addr:neighbourhood}",
+                    "{addr:quarter}",
+                    "{addr:neighbourhood}",
+                ],
+            ),
+            ("region", &["{addr:province}", "{addr:region}"]),
+        ],
+    ),
+];
+
+const CONSCRIPTION_SCHEME: Fields = &[
+    (
+        "number",
+        &[
+            "{addr:conscriptionnumber}/{addr:streetnumber}",
+            "{addr:housenumber}",
+            "{addr:conscriptionnumber}",
+        ],
+    ),
+    ("street", &["{addr:street}", "{addr:place}"]),
+];
+
+/// Part of a template.
+#[derive(Clone, Debug)]
+enum Part {
+    Text(String),
+    Tag(String),
+}
+
+/// Value built from the tags of an object, such as `"{addr:block_number}-{addr:housenumber}"`.
+#[derive(Clone, Debug)]
+struct Template(Vec<Part>);
+
+impl Template {
+    fn parse(template: &str) -> Result<Self, std::string::String> {
+        let mut parts = Vec::new();
+        let mut rest = template;
+
+        while let Some(start) = rest.find('{') {
+            let end = rest[start..]
+                .find('}')
+                .ok_or_else(|| format!("unclosed `{{` in template `{}`", template))?;
+
+            if start > 0 {
+                parts.push(Part::Text(rest[..start].into()));
+            }
+
+            parts.push(Part::Tag(rest[start + 1..start + end].into()));
+            rest = &rest[start + end + 1..];
+        }
+
+        if !rest.is_empty() {
+            parts.push(Part::Text(rest.into()));
+        }
+
+        if !parts.iter().any(|part| matches!(part, Part::Tag(_))) {
+            return Err(format!("template `{}` doesn't use any tag", template));
+        }
+
+        Ok(Self(parts))
+    }
+
+    /// Build the value, if all the tags used by the template are set.
+    fn build(&self, tags: &Tags) -> Option<String> {
+        let mut value = String::new();
+
+        for part in &self.0 {
+            match part {
+                Part::Text(text) => value.push_str(text),
+                Part::Tag(key) => value.push_str(tags.get(key.as_str())?),
+            }
+        }
+
+        Some(value)
+    }
+}
+
+/// Templates used to build each field of an address, an empty list means that the field is not
+/// specified by the scheme.
+#[derive(Clone, Debug, Default)]
+struct TagScheme {
+    number: Vec<Template>,
+    street: Vec<Template>,
+    unit: Vec<Template>,
+    city: Vec<Template>,
+    district: Vec<Template>,
+    region: Vec<Template>,
+    postcode: Vec<Template>,
+}
+
+impl TagScheme {
+    fn field_mut(&mut self, field: &str) -> Result<&mut Vec<Template>, std::string::String> {
+        Ok(match field {
+            "number" => &mut self.number,
+            "street" => &mut self.street,
+            "unit" => &mut self.unit,
+            "city" => &mut self.city,
+            "district" => &mut self.district,
+            "region" => &mut self.region,
+            "postcode" => &mut self.postcode,
+            _ => return Err(format!("unknown address field `{}`", field)),
+        })
+    }
+
+    /// Replace the templates of the given fields.
+    fn set_fields<'t>(
+        &mut self,
+        fields: impl IntoIterator<Item = (&'t str, impl IntoIterator<Item = &'t str>)>,
+    ) -> Result<(), std::string::String> {
+        for (field, templates) in fields {
+            *self.field_mut(field)? = templates
+                .into_iter()
+                .map(Template::parse)
+                .collect::<Result<_, _>>()?;
+        }
+
+        Ok(())
+    }
+
+    fn from_fields(fields: &[(&str, &[&str])]) -> Self {
+        let mut scheme = Self::default();
+        scheme
+            .set_fields(
+                fields
+                    .iter()
+                    .map(|(field, templates)| (*field, templates.iter().copied())),
+            )
+            .expect("invalid built-in tag scheme");
+        scheme
+    }
+}
+
+/// Tag schemes of all countries, see the module documentation.
+#[derive(Clone, Debug)]
+pub struct TagSchemes {
+    default: TagScheme,
+    /// Schemes of countries, identified by their ISO 3166-1 code
+    countries: Vec<(String, TagScheme)>,
+}
+
+impl Default for TagSchemes {
+    /// Built-in schemes.
+    fn default() -> Self {
+        Self {
+            default: TagScheme::from_fields(DEFAULT_SCHEME),
+            countries: COUNTRY_SCHEMES
+                .iter()
+                .map(|(country, fields)| ((*country).into(), TagScheme::from_fields(fields)))
+                .collect(),
+        }
+    }
+}
+
+impl TagSchemes {
+    /// Built-in schemes overridden with the content of a JSON file.
+    pub fn from_file(path: &Path) -> Result<Self, std::string::String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("failed to read {:?}: {}", path, err))?;
+
+        let overrides: HashMap<
+            std::string::String,
+            HashMap<std::string::String, Vec<std::string::String>>,
+        > = serde_json::from_str(&content)
+            .map_err(|err| format!("invalid tag schemes in {:?}: {}", path, err))?;
+
+        let mut schemes = Self::default();
+
+        for (country, fields) in &overrides {
+            let scheme = match country.as_str() {
+                "default" => &mut schemes.default,
+                country => match schemes.country_index(country) {
+                    Some(index) => &mut schemes.countries[index].1,
+                    None => {
+                        schemes
+                            .countries
+                            .push((country.to_uppercase().into(), TagScheme::default()));
+                        &mut schemes.countries.last_mut().unwrap().1
+                    }
+                },
+            };
+
+            scheme
+                .set_fields(fields.iter().map(|(field, templates)| {
+                    (field.as_str(), templates.iter().map(|t| t.as_str()))
+                }))
+                .map_err(|err| {
+                    format!("invalid tag scheme `{}` in {:?}: {}", country, path, err)
+                })?;
+        }
+
+        Ok(schemes)
+    }
+
+    fn country_index(&self, country: &str) -> Option<usize> {
+        self.countries
+            .iter()
+            .position(|(code, _)| code.eq_ignore_ascii_case(country))
+    }
+
+    /// Build the value of a field, using the scheme of the country of the object.
+    fn build<'s>(
+        &'s self,
+        tags: &Tags,
+        default_country: Option<&str>,
+        field: impl Fn(&'s TagScheme) -> &'s [Template],
+    ) -> Option<String> {
+        let country_templates = tags
+            .get("addr:country")
+            .map(|country| country.as_str())
+            .or(default_country)
+            .and_then(|country| self.country_index(country))
+            .map(|index| field(&self.countries[index].1))
+            .filter(|templates| !templates.is_empty());
+
+        country_templates
+            .unwrap_or_else(|| field(&self.default))
+            .iter()
+            .find_map(|template| template.build(tags))
+    }
+
+    /// Returns the housenumber and the street of an object, if it has both.
+    pub(crate) fn number_and_street(
+        &self,
+        tags: &Tags,
+        default_country: Option<&str>,
+    ) -> Option<(String, String)> {
+        // Most objects don't have any address, don't bother looking at their scheme
+        if !tags.keys().any(|key| key.starts_with("addr:")) {
+            return None;
+        }
+
+        Some((
+            self.build(tags, default_country, |s| &s.number)?,
+            self.build(tags, default_country, |s| &s.street)?,
+        ))
+    }
+
+    /// Fill the fields of an address from the tags of an object.
+    pub(crate) fn fill(&self, address: &mut Address, tags: &Tags, default_country: Option<&str>) {
+        address.number = self.build(tags, default_country, |s| &s.number);
+        address.street = self.build(tags, default_country, |s| &s.street);
+        address.unit = self.build(tags, default_country, |s| &s.unit);
+        address.city = self.build(tags, default_country, |s| &s.city);
+        address.district = self.build(tags, default_country, |s| &s.district);
+        address.region = self.build(tags, default_country, |s| &s.region);
+        address.postcode = self.build(tags, default_country, |s| &s.postcode);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(kv: &[(&str, &str)]) -> Tags {
+        let mut tags = Tags::new();
+
+        for (k, v) in kv {
+            tags.insert((*k).into(), (*v).into());
+        }
+
+        tags
+    }
+
+    #[test]
+    fn check_schemes() {
+        let schemes = TagSchemes::default();
+        let mut addr = Address::default();
+
+        let czech = tags(&[
+            ("addr:conscriptionnumber", "1234"),
+            ("addr:streetnumber", "5"),
+            ("addr:place", "Lhota"),
+            ("addr:suburb", "Dolní Lhota"),
+        ]);
+        schemes.fill(&mut addr, &czech, Some("cz"));
+        assert_eq!(addr.number.as_deref(), Some("1234/5"));
+        assert_eq!(addr.street.as_deref(), Some("Lhota"));
+        assert_eq!(addr.district.as_deref(), Some("Dolní Lhota"));
+
+        // Without a country, the default scheme doesn't know these tags
+        assert_eq!(schemes.number_and_street(&czech, None), None);
+
+        let japanese = tags(&[
+            ("addr:country", "JP"),
+            ("addr:province", "東京都"),
+            ("addr:quarter", "丸の内"),
+            ("addr:neighbourhood", "一丁目"),
+            ("addr:block_number", "9"),
+            ("addr:housenumber", "1"),
+        ]);
+        schemes.fill(&mut addr, &japanese, None);
+        assert_eq!(addr.number.as_deref(), Some("9-1"));
+        assert_eq!(addr.street.as_deref(), Some("丸の内一丁目"));
+        assert_eq!(addr.region.as_deref(), Some("東京都"));
+
+        assert!(Template::parse("{addr:housenumber").is_err());
+        assert!(Template::parse("no tag").is_err());
+    }
+}
@@ -0,0 +1,223 @@
+//! Reader for the **OSM XML** format (`.osm` files), producing the same objects as the PBF
+//! reader. The **OsmChange** format (`.osc` files) is also supported, see
+//! [`OsmXmlReader::action`].
+
+use std::io::BufRead;
+
+use osmpbfreader::objects::{
+    Node, NodeId, OsmId, OsmObj, Ref, Relation, RelationId, Tags, Way, WayId,
+};
+use quick_xml::events::{BytesStart, Event};
+use smartstring::alias::String;
+
+use crate::info::{parse_timestamp, Info};
+
+/// Kind of change applied to the objects of an **OsmChange** document.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChangeAction {
+    Create,
+    Modify,
+    Delete,
+}
+
+/// Iterator over the objects of an OSM XML document.
+pub struct OsmXmlReader<R: BufRead> {
+    reader: quick_xml::Reader<R>,
+    buf: Vec<u8>,
+    /// Object currently being read, it is complete when its closing tag is reached.
+    current: Option<OsmObj>,
+    /// Version and timestamp of the current object.
+    info: Info,
+    /// Block of an OsmChange document currently being read.
+    action: Option<ChangeAction>,
+    done: bool,
+}
+
+impl<R: BufRead> OsmXmlReader<R> {
+    pub fn new(input: R) -> Self {
+        Self {
+            reader: quick_xml::Reader::from_reader(input),
+            buf: Vec::new(),
+            current: None,
+            info: Info::default(),
+            action: None,
+            done: false,
+        }
+    }
+
+    /// Returns the kind of change applied to the last object returned by the iterator, which is
+    /// `None` outside of an OsmChange document.
+    pub fn action(&self) -> Option<ChangeAction> {
+        self.action
+    }
+
+    /// Read the next event of the document and update current object with it. Returns a complete
+    /// object, with its version and timestamp, when one is available.
+    fn read_event(&mut self) -> Result<Option<(OsmObj, Info)>, std::string::String> {
+        self.buf.clear();
+        let event = self.reader.read_event_into(&mut self.buf).map_err(|err| {
+            self.done = true;
+            format!(
+                "invalid XML at position {}: {}",
+                self.reader.buffer_position(),
+                err
+            )
+        })?;
+
+        let (elem, is_empty) = match event {
+            Event::Start(elem) => (elem, false),
+            Event::Empty(elem) => (elem, true),
+            Event::End(elem) => {
+                return Ok(match elem.name().as_ref() {
+                    b"node" | b"way" | b"relation" => self.take_current(),
+                    b"create" | b"modify" | b"delete" => {
+                        self.action = None;
+                        None
+                    }
+                    _ => None,
+                })
+            }
+            Event::Eof => {
+                self.done = true;
+                return Ok(None);
+            }
+            _ => return Ok(None),
+        };
+
+        if let b"node" | b"way" | b"relation" = elem.name().as_ref() {
+            self.info = elem_info(&elem);
+        }
+
+        match elem.name().as_ref() {
+            b"create" => self.action = Some(ChangeAction::Create),
+            b"modify" => self.action = Some(ChangeAction::Modify),
+            b"delete" => self.action = Some(ChangeAction::Delete),
+            b"node" => {
+                // Deleted nodes are usually given without their location.
+                let parse_coord = |key| match self.action {
+                    Some(ChangeAction::Delete) => Ok(parse_attr(&elem, key).unwrap_or(0.)),
+                    _ => parse_attr(&elem, key),
+                };
+
+                let node = Node {
+                    id: NodeId(parse_attr(&elem, "id")?),
+                    tags: Tags::new(),
+                    decimicro_lat: to_decimicro(parse_coord("lat")?),
+                    decimicro_lon: to_decimicro(parse_coord("lon")?),
+                };
+                self.current = Some(OsmObj::Node(node));
+            }
+            b"way" => {
+                let way = Way {
+                    id: WayId(parse_attr(&elem, "id")?),
+                    tags: Tags::new(),
+                    nodes: Vec::new(),
+                };
+                self.current = Some(OsmObj::Way(way));
+            }
+            b"relation" => {
+                let relation = Relation {
+                    id: RelationId(parse_attr(&elem, "id")?),
+                    tags: Tags::new(),
+                    refs: Vec::new(),
+                };
+                self.current = Some(OsmObj::Relation(relation));
+            }
+            b"tag" => {
+                let tags = match &mut self.current {
+                    Some(OsmObj::Node(n)) => &mut n.tags,
+                    Some(OsmObj::Way(w)) => &mut w.tags,
+                    Some(OsmObj::Relation(r)) => &mut r.tags,
+                    None => return Ok(None),
+                };
+                tags.insert(get_attr(&elem, "k")?, get_attr(&elem, "v")?);
+            }
+            b"nd" => {
+                if let Some(OsmObj::Way(w)) = &mut self.current {
+                    w.nodes.push(NodeId(parse_attr(&elem, "ref")?));
+                }
+            }
+            b"member" => {
+                if let Some(OsmObj::Relation(r)) = &mut self.current {
+                    let id = parse_attr(&elem, "ref")?;
+                    let member = match get_attr(&elem, "type")?.as_str() {
+                        "node" => OsmId::Node(NodeId(id)),
+                        "way" => OsmId::Way(WayId(id)),
+                        "relation" => OsmId::Relation(RelationId(id)),
+                        other => return Err(format!("unknown member type `{}`", other)),
+                    };
+                    let role = get_attr(&elem, "role").unwrap_or_default();
+                    r.refs.push(Ref { member, role });
+                }
+            }
+            _ => {}
+        }
+
+        if is_empty {
+            if let b"node" | b"way" | b"relation" = elem.name().as_ref() {
+                return Ok(self.take_current());
+            }
+        }
+
+        Ok(None)
+    }
+
+    fn take_current(&mut self) -> Option<(OsmObj, Info)> {
+        Some((self.current.take()?, std::mem::take(&mut self.info)))
+    }
+}
+
+impl<R: BufRead> Iterator for OsmXmlReader<R> {
+    type Item = Result<(OsmObj, Info), std::string::String>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            match self.read_event() {
+                Ok(Some(obj)) => return Some(Ok(obj)),
+                Ok(None) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+}
+
+/// Convert a coordinate in degrees into decimicro degrees.
+pub(crate) fn to_decimicro(coord: f64) -> i32 {
+    (coord * 1e7).round() as i32
+}
+
+/// Returns the version and the timestamp of an object, which are optional.
+fn elem_info(elem: &BytesStart) -> Info {
+    let timestamp = get_attr(elem, "timestamp").ok();
+
+    Info {
+        version: parse_attr(elem, "version").ok(),
+        timestamp: timestamp.and_then(|timestamp| parse_timestamp(&timestamp)),
+    }
+}
+
+/// Returns the unescaped value of an attribute.
+fn get_attr(elem: &BytesStart, key: &str) -> Result<String, std::string::String> {
+    let attr = elem
+        .try_get_attribute(key)
+        .map_err(|err| format!("invalid attribute `{}`: {}", key, err))?
+        .ok_or_else(|| format!("missing attribute `{}`", key))?;
+
+    let value = attr
+        .unescape_value()
+        .map_err(|err| format!("invalid attribute `{}`: {}", key, err))?;
+
+    Ok(value.as_ref().into())
+}
+
+/// Returns the value of an attribute parsed as a number.
+fn parse_attr<T: std::str::FromStr>(
+    elem: &BytesStart,
+    key: &str,
+) -> Result<T, std::string::String> {
+    get_attr(elem, key)?
+        .parse()
+        .map_err(|_| format!("invalid value for attribute `{}`", key))
+}
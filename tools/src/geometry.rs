@@ -0,0 +1,95 @@
+//! Locations of addresses read from GeoJSON geometries, which aren't always points.
+
+use serde_json::Value;
+
+/// Why a geometry can't be used as the location of an address.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GeometryError {
+    /// The geometry isn't a point, a multipoint, a polygon or a multipolygon.
+    Unsupported,
+    /// The coordinates of the geometry are missing, malformed or out of bounds.
+    InvalidCoordinates,
+}
+
+/// Check that a longitude and a latitude are within their bounds.
+pub fn valid_coordinates(lon: f64, lat: f64) -> bool {
+    (-180. ..=180.).contains(&lon) && (-90. ..=90.).contains(&lat)
+}
+
+/// Read a position, which is an array of at least two numbers: the longitude then the latitude.
+fn position(value: &Value) -> Option<[f64; 2]> {
+    match value.as_array()?.as_slice() {
+        [lon, lat, ..] => {
+            let (lon, lat) = (lon.as_f64()?, lat.as_f64()?);
+            valid_coordinates(lon, lat).then_some([lon, lat])
+        }
+        _ => None,
+    }
+}
+
+/// Compute the centroid of a ring given as an array of positions, falling back to the average of
+/// its vertices when its area is null.
+fn ring_centroid(value: &Value) -> Option<[f64; 2]> {
+    let ring = value
+        .as_array()?
+        .iter()
+        .map(position)
+        .collect::<Option<Vec<_>>>()?;
+
+    if ring.is_empty() {
+        return None;
+    }
+
+    let (mut area, mut lon, mut lat) = (0., 0., 0.);
+
+    for (a, b) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        let cross = a[0] * b[1] - b[0] * a[1];
+        area += cross;
+        lon += (a[0] + b[0]) * cross;
+        lat += (a[1] + b[1]) * cross;
+    }
+
+    if area.abs() > f64::EPSILON {
+        Some([lon / (3. * area), lat / (3. * area)])
+    } else {
+        let count = ring.len() as f64;
+        Some([
+            ring.iter().map(|pos| pos[0]).sum::<f64>() / count,
+            ring.iter().map(|pos| pos[1]).sum::<f64>() / count,
+        ])
+    }
+}
+
+/// The point used as the location of an address read from a GeoJSON geometry of type `kind`: the
+/// point itself, the first point of a multipoint, or the centroid of the exterior ring of a polygon
+/// (of the first polygon of a multipolygon). Returns `[lon, lat]`.
+///
+/// Example:
+///
+/// ```
+/// use serde_json::json;
+/// use tools::{representative_point, GeometryError};
+///
+/// let square = json!([[[0., 0.], [2., 0.], [2., 2.], [0., 2.], [0., 0.]]]);
+/// assert_eq!(representative_point("Polygon", &square), Ok([1., 1.]));
+///
+/// let line = json!([[0., 0.], [2., 2.]]);
+/// assert_eq!(
+///     representative_point("LineString", &line),
+///     Err(GeometryError::Unsupported),
+/// );
+/// ```
+pub fn representative_point(kind: &str, coordinates: &Value) -> Result<[f64; 2], GeometryError> {
+    let point = match kind {
+        "Point" => position(coordinates),
+        "MultiPoint" => coordinates.get(0).and_then(position),
+        "Polygon" => coordinates.get(0).and_then(ring_centroid),
+        "MultiPolygon" => coordinates
+            .get(0)
+            .and_then(|x| x.get(0))
+            .and_then(ring_centroid),
+        _ => return Err(GeometryError::Unsupported),
+    };
+
+    point.ok_or(GeometryError::InvalidCoordinates)
+}
@@ -0,0 +1,246 @@
+//! Input files of the importers, which may be compressed or archived.
+//!
+//! The compression of a file is detected from its first bytes rather than from its name, so that
+//! downloads can be read as they are. Zip archives, such as the batches of **OpenAddresses**, hold
+//! several files which are read one after the other, each of them possibly compressed as well.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
+
+/// Size of the read buffer put on top of input files
+const READ_BUFFER_SIZE: usize = 1024 * 1024; // 1MB
+
+/// Extensions of compressed files, see [`strip_compression_extension`].
+const COMPRESSION_EXTENSIONS: &[&str] = &["gz", "bz2", "zst", "zip"];
+
+/// Compression of an input, detected from its first bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Bzip2,
+    Zstd,
+    /// A zip archive, which may hold several files.
+    Zip,
+}
+
+impl Compression {
+    /// Detect the compression of an input from its first bytes.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use tools::Compression;
+    ///
+    /// assert_eq!(Compression::detect(b"\x1f\x8b\x08\x00"), Compression::Gzip);
+    /// assert_eq!(Compression::detect(b"id;numero"), Compression::None);
+    /// ```
+    pub fn detect(magic: &[u8]) -> Self {
+        match magic {
+            [0x1f, 0x8b, ..] => Self::Gzip,
+            [b'B', b'Z', b'h', ..] => Self::Bzip2,
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Self::Zstd,
+            [b'P', b'K', 0x03, 0x04, ..] => Self::Zip,
+            _ => Self::None,
+        }
+    }
+}
+
+/// Decompress a stream on the fly, depending on its first bytes.
+fn decompress<'a>(mut reader: impl BufRead + 'a) -> io::Result<Box<dyn BufRead + 'a>> {
+    let compression = Compression::detect(reader.fill_buf()?);
+
+    let buffered = |inner: Box<dyn Read + 'a>| -> Box<dyn BufRead + 'a> {
+        Box::new(BufReader::with_capacity(READ_BUFFER_SIZE, inner))
+    };
+
+    Ok(match compression {
+        Compression::None => Box::new(reader),
+        Compression::Gzip => buffered(Box::new(MultiGzDecoder::new(reader))),
+        Compression::Bzip2 => buffered(Box::new(MultiBzDecoder::new(reader))),
+        Compression::Zstd => buffered(Box::new(zstd::Decoder::with_buffer(reader)?)),
+        Compression::Zip => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected zip archive, its entries must be read with `for_each_entry`",
+            ))
+        }
+    })
+}
+
+/// Open a file, which is decompressed on the fly when it is compressed with gzip, bzip2 or zstd.
+/// An error is returned for zip archives, which must be read with [`for_each_entry`].
+///
+/// Example:
+///
+/// ```no_run
+/// use std::io::BufRead;
+/// use tools::open_input;
+///
+/// let input = open_input("adresses-75.csv.gz".as_ref()).expect("failed to open input");
+/// println!("{} lines", input.lines().count());
+/// ```
+pub fn open_input(path: &Path) -> io::Result<Box<dyn BufRead>> {
+    let file = File::open(path)?;
+    decompress(BufReader::with_capacity(READ_BUFFER_SIZE, file))
+}
+
+/// Call `f` with the name and the content of each file held by the input at `path`: every entry
+/// of a zip archive, or the file itself otherwise. Files are decompressed on the fly, see
+/// [`open_input`].
+///
+/// The name of entries is their path in the archive, and `f` may return early for the ones it
+/// doesn't need by not reading them.
+///
+/// Example:
+///
+/// ```no_run
+/// use std::io::BufRead;
+/// use tools::for_each_entry;
+///
+/// for_each_entry("fr.zip".as_ref(), |name, input| {
+///     println!("{}: {} lines", name.display(), input.lines().count());
+/// })
+/// .expect("failed to read input");
+/// ```
+pub fn for_each_entry(path: &Path, mut f: impl FnMut(&Path, &mut dyn BufRead)) -> io::Result<()> {
+    let mut file = BufReader::with_capacity(READ_BUFFER_SIZE, File::open(path)?);
+
+    if Compression::detect(file.fill_buf()?) != Compression::Zip {
+        f(path, &mut decompress(file)?);
+        return Ok(());
+    }
+
+    let mut archive = zip::ZipArchive::new(file.into_inner())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    for index in 0..archive.len() {
+        let entry = archive
+            .by_index(index)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        if entry.is_dir() {
+            continue;
+        }
+
+        let name = PathBuf::from(entry.name());
+        let mut input = decompress(BufReader::with_capacity(READ_BUFFER_SIZE, entry))?;
+        f(&name, &mut input);
+    }
+
+    Ok(())
+}
+
+/// Returns `path` without the extension of its compression, if any: `fr/paris.geojson.gz` gives
+/// `fr/paris.geojson`. This is used to know the format of compressed files from their name.
+///
+/// Example:
+///
+/// ```
+/// use std::path::Path;
+/// use tools::strip_compression_extension;
+///
+/// let path = Path::new("fr/paris.geojson.gz");
+/// assert_eq!(strip_compression_extension(path), Path::new("fr/paris.geojson"));
+/// ```
+pub fn strip_compression_extension(path: &Path) -> PathBuf {
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some(ext) if COMPRESSION_EXTENSIONS.contains(&ext) => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    const CONTENT: &str = "id;numero\n1;12\n";
+
+    fn read_all(path: &Path) -> Vec<(PathBuf, String)> {
+        let mut entries = Vec::new();
+
+        for_each_entry(path, |name, input| {
+            let mut content = String::new();
+            input.read_to_string(&mut content).unwrap();
+            entries.push((name.to_path_buf(), content));
+        })
+        .unwrap();
+
+        entries
+    }
+
+    #[test]
+    fn check_strip_compression_extension() {
+        let strip = |path: &str| strip_compression_extension(Path::new(path));
+        assert_eq!(strip("fr/paris.geojson.gz"), Path::new("fr/paris.geojson"));
+        assert_eq!(strip("fr/paris.csv.zst"), Path::new("fr/paris.csv"));
+        assert_eq!(strip("fr/paris.csv"), Path::new("fr/paris.csv"));
+        assert_eq!(strip("paris.gz"), Path::new("paris"));
+
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+
+            // Names which aren't valid UTF-8 are kept as they are
+            let path = Path::new(OsStr::from_bytes(b"fr/pa\xffris.csv.gz"));
+            let expected = Path::new(OsStr::from_bytes(b"fr/pa\xffris.csv"));
+            assert_eq!(strip_compression_extension(path), expected);
+        }
+    }
+
+    #[test]
+    fn check_for_each_entry() {
+        let dir = std::env::temp_dir();
+
+        let plain = dir.join("check_input.csv");
+        std::fs::write(&plain, CONTENT).unwrap();
+
+        let gzip = dir.join("check_input.csv.gz");
+        let mut encoder =
+            flate2::write::GzEncoder::new(File::create(&gzip).unwrap(), Default::default());
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let bzip2 = dir.join("check_input.csv.bz2");
+        let mut encoder =
+            bzip2::write::BzEncoder::new(File::create(&bzip2).unwrap(), Default::default());
+        encoder.write_all(CONTENT.as_bytes()).unwrap();
+        encoder.finish().unwrap();
+
+        let zstd = dir.join("check_input.csv.zst");
+        std::fs::write(&zstd, zstd::encode_all(CONTENT.as_bytes(), 0).unwrap()).unwrap();
+
+        let archive = dir.join("check_input.zip");
+        let mut writer = zip::ZipWriter::new(File::create(&archive).unwrap());
+        writer.add_directory("fr", Default::default()).unwrap();
+        writer.start_file("fr/a.csv", Default::default()).unwrap();
+        writer.write_all(CONTENT.as_bytes()).unwrap();
+        writer
+            .start_file("fr/b.csv.zst", Default::default())
+            .unwrap();
+        writer.write_all(&std::fs::read(&zstd).unwrap()).unwrap();
+        writer.finish().unwrap();
+
+        for path in [&plain, &gzip, &bzip2, &zstd] {
+            assert_eq!(read_all(path), [(path.clone(), CONTENT.to_string())]);
+        }
+
+        assert_eq!(
+            read_all(&archive),
+            [
+                (PathBuf::from("fr/a.csv"), CONTENT.to_string()),
+                (PathBuf::from("fr/b.csv.zst"), CONTENT.to_string()),
+            ]
+        );
+
+        for path in [plain, gzip, bzip2, zstd, archive] {
+            let _ = std::fs::remove_file(path); // we ignore any potential error
+        }
+    }
+}
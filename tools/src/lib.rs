@@ -3,6 +3,42 @@ use serde::{Deserialize, Serialize};
 use smartstring::alias::String;
 use std::fs;
 
+mod geometry;
+mod input;
+mod mapping;
+mod projection;
+mod rejections;
+mod sink;
+
+pub use crate::geometry::{representative_point, valid_coordinates, GeometryError};
+pub use crate::input::{for_each_entry, open_input, strip_compression_extension, Compression};
+pub use crate::mapping::{AddressMapping, Column, FieldMapping, Record};
+pub use crate::projection::{Crs, ProjectionError};
+pub use crate::rejections::{Rejection, Rejections};
+pub use crate::sink::{AddressSink, LockedDB};
+
+/// Columns that were added to the tables after they were first released, with their type. They
+/// are added to databases created before them when these are opened.
+const ADDED_COLUMNS: &[(&str, &str)] = &[
+    ("source_id", "TEXT"),
+    ("name", "TEXT"),
+    ("object_kind", "TEXT"),
+    ("building", "TEXT"),
+    ("entrance", "TEXT"),
+    ("footprint_area", "REAL"),
+    ("version", "INTEGER"),
+    ("timestamp", "INTEGER"),
+    ("certified", "INTEGER"),
+    ("origin", "TEXT"),
+    ("country", "TEXT"),
+    ("source_file", "TEXT"),
+];
+
+/// Columns of the `addresses` table, in the order used to insert and select addresses.
+const ADDRESS_COLUMNS: &str = "lat, lon, number, street, unit, city, district, region, postcode, \
+    source_id, name, object_kind, building, entrance, footprint_area, version, timestamp, certified, \
+    origin, country, source_file";
+
 /// Returns a structure that displays as the current time under the form "HH:MM:SS".
 pub fn get_time() -> impl std::fmt::Display {
     struct FormatTime(time::OffsetDateTime);
@@ -83,12 +119,50 @@ pub struct Address {
     pub district: Option<String>,
     pub region: Option<String>,
     pub postcode: Option<String>,
+    /// Identifier of the object this address was read from in its source, such as `node/123` for
+    /// **OpenStreetMap**. It isn't part of the address itself.
+    pub source_id: Option<String>,
+    /// Information about the object this address was read from.
+    pub meta: AddressMeta,
+}
+
+/// Optional information about the object an address was read from. It isn't part of the address
+/// itself, but may help to choose between duplicates.
+#[derive(Clone, Debug, Default, PartialOrd, PartialEq)]
+pub struct AddressMeta {
+    /// Name of the object, such as the name of a shop.
+    pub name: Option<String>,
+    /// Kind of the object in its source, such as `node`, `way` or `relation` for
+    /// **OpenStreetMap**.
+    pub object_kind: Option<String>,
+    /// Type of the building holding the address, such as `house` or `yes`.
+    pub building: Option<String>,
+    /// Type of the entrance holding the address, such as `main` or `yes`.
+    pub entrance: Option<String>,
+    /// Area of the footprint of the building holding the address, in square meters.
+    pub footprint_area: Option<f64>,
+    /// Version of the object in its source.
+    pub version: Option<i64>,
+    /// Time of the last edit of the object, as a Unix timestamp in seconds.
+    pub timestamp: Option<i64>,
+    /// Whether the address was certified by an authority, such as the municipality for the
+    /// **Base Adresse Nationale**.
+    pub certified: Option<bool>,
+    /// Source the address was taken from by an aggregated source, such as `OSM` or `CAD`
+    /// (cadastre) for **BANO**.
+    pub origin: Option<String>,
+    /// ISO 3166-1 alpha-2 code of the country of the address, in upper case, when the source tells
+    /// it, such as the folders of **OpenAddresses**.
+    pub country: Option<String>,
+    /// Path of the file the address was read from, relative to the top of its source tree, such
+    /// as `us/ca/san_francisco` for **OpenAddresses**.
+    pub source_file: Option<String>,
 }
 
 impl Address {
     pub const NB_FIELDS: usize = 9;
 
-    /// Returns the number of not empty fields.
+    /// Returns the number of not empty fields, `source_id` and `meta` aren't counted.
     ///
     /// Example:
     ///
@@ -105,6 +179,8 @@ impl Address {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// };
     /// assert_eq!(addr.count_non_empty_fields(), 3);
     /// ```
@@ -131,6 +207,14 @@ impl<'r> TryFrom<&Row<'r>> for Address {
             )
         };
 
+        // Tables filled by other tools may not have the columns added over time.
+        fn optional<T>(res: rusqlite::Result<Option<T>>) -> rusqlite::Result<Option<T>> {
+            match res {
+                Err(rusqlite::Error::InvalidColumnName(_)) => Ok(None),
+                res => res,
+            }
+        }
+
         Ok(Address {
             lat: row.get("lat")?,
             lon: row.get("lon")?,
@@ -141,10 +225,41 @@ impl<'r> TryFrom<&Row<'r>> for Address {
             district: get_string("district")?,
             region: get_string("region")?,
             postcode: get_string("postcode")?,
+            source_id: optional(get_string("source_id"))?,
+            meta: AddressMeta {
+                name: optional(get_string("name"))?,
+                object_kind: optional(get_string("object_kind"))?,
+                building: optional(get_string("building"))?,
+                entrance: optional(get_string("entrance"))?,
+                footprint_area: optional(row.get("footprint_area"))?,
+                version: optional(row.get("version"))?,
+                timestamp: optional(row.get("timestamp"))?,
+                certified: optional(row.get("certified"))?,
+                origin: optional(get_string("origin"))?,
+                country: optional(get_string("country"))?,
+                source_file: optional(get_string("source_file"))?,
+            },
         })
     }
 }
 
+/// A named street, which may not have any address.
+#[derive(Clone, Debug, Default, PartialOrd, PartialEq)]
+pub struct Street {
+    pub name: String,
+    pub city: Option<String>,
+    /// Location of the centroid of the street.
+    pub lat: f64,
+    pub lon: f64,
+    /// Bounding box of the street.
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+    /// Identifier of the object this street was read from in its source, such as `way/123`.
+    pub source_id: Option<String>,
+}
+
 /// Legacy OpenAddress CSV format
 #[derive(Deserialize, Serialize)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
@@ -173,7 +288,7 @@ impl From<Address> for OpenAddressLegacy {
             district: address.district.unwrap_or_default(),
             region: address.region.unwrap_or_default(),
             postcode: address.postcode.unwrap_or_default(),
-            id: String::new(),
+            id: address.source_id.unwrap_or_default(),
         }
     }
 }
@@ -198,6 +313,8 @@ impl From<OpenAddressLegacy> for Address {
             district: filter_empty(val.district),
             region: filter_empty(val.region),
             postcode: filter_empty(val.postcode),
+            source_id: filter_empty(val.id),
+            meta: AddressMeta::default(),
         }
     }
 }
@@ -208,6 +325,7 @@ impl From<OpenAddressLegacy> for Address {
 pub struct DB {
     conn: Connection,
     buffer: Vec<Address>,
+    streets_buffer: Vec<Street>,
     db_buffer_size: usize,
 }
 
@@ -242,6 +360,8 @@ impl DB {
                 .expect("failed to drop addresses");
             conn.execute("DROP TABLE IF EXISTS addresses_errors", [])
                 .expect("failed to drop errors");
+            conn.execute("DROP TABLE IF EXISTS streets", [])
+                .expect("failed to drop streets");
         }
         conn.execute(
             r#"CREATE TABLE IF NOT EXISTS addresses(
@@ -254,6 +374,18 @@ impl DB {
                 district TEXT,
                 region TEXT,
                 postcode TEXT,
+                source_id TEXT,
+                name TEXT,
+                object_kind TEXT,
+                building TEXT,
+                entrance TEXT,
+                footprint_area REAL,
+                version INTEGER,
+                timestamp INTEGER,
+                certified INTEGER,
+                origin TEXT,
+                country TEXT,
+                source_file TEXT,
                 PRIMARY KEY (lat, lon, number, street, city)
             )"#,
             [],
@@ -270,14 +402,64 @@ impl DB {
                 district TEXT,
                 region TEXT,
                 postcode TEXT,
+                source_id TEXT,
+                name TEXT,
+                object_kind TEXT,
+                building TEXT,
+                entrance TEXT,
+                footprint_area REAL,
+                version INTEGER,
+                timestamp INTEGER,
+                certified INTEGER,
+                origin TEXT,
+                country TEXT,
+                source_file TEXT,
                 kind TEXT
             )"#,
             [],
         )
         .map_err(|e| format!("failed to create error table: {}", e))?;
+        conn.execute(
+            r#"CREATE TABLE IF NOT EXISTS streets(
+                name TEXT NOT NULL,
+                city TEXT,
+                lat REAL NOT NULL,
+                lon REAL NOT NULL,
+                min_lat REAL NOT NULL,
+                min_lon REAL NOT NULL,
+                max_lat REAL NOT NULL,
+                max_lon REAL NOT NULL,
+                source_id TEXT
+            )"#,
+            [],
+        )
+        .map_err(|e| format!("failed to create streets table: {}", e))?;
+
+        // Databases created before some columns were introduced don't have them yet.
+        for table in ["addresses", "addresses_errors"] {
+            for (column, kind) in ADDED_COLUMNS {
+                if conn
+                    .prepare(&format!("SELECT {} FROM {}", column, table))
+                    .is_err()
+                {
+                    conn.execute(
+                        &format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, kind),
+                        [],
+                    )
+                    .map_err(|e| format!("failed to add {} to {}: {}", column, table, e))?;
+                }
+            }
+        }
+
+        conn.execute(
+            "CREATE INDEX IF NOT EXISTS addresses_source_id ON addresses(source_id)",
+            [],
+        )
+        .map_err(|e| format!("failed to create index: {}", e))?;
         Ok(DB {
             conn,
             buffer: Vec::with_capacity(db_buffer_size),
+            streets_buffer: Vec::new(),
             db_buffer_size,
         })
     }
@@ -300,6 +482,8 @@ impl DB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// db.flush();
     /// ```
@@ -309,19 +493,11 @@ impl DB {
 
         let mut errors = {
             let mut stmt = tx
-                .prepare(
-                    "INSERT INTO addresses(
-                    lat,
-                    lon,
-                    number,
-                    street,
-                    unit,
-                    city,
-                    district,
-                    region,
-                    postcode
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
-                )
+                .prepare(&format!(
+                    "INSERT INTO addresses({}) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, \
+                    ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21)",
+                    ADDRESS_COLUMNS
+                ))
                 .expect("failed to prepare statement");
 
             self.buffer
@@ -337,6 +513,18 @@ impl DB {
                         &obj.district.as_ref().map(|s| s.as_str()),
                         &obj.region.as_ref().map(|s| s.as_str()),
                         &obj.postcode.as_ref().map(|s| s.as_str()),
+                        &obj.source_id.as_ref().map(|s| s.as_str()),
+                        &obj.meta.name.as_ref().map(|s| s.as_str()),
+                        &obj.meta.object_kind.as_ref().map(|s| s.as_str()),
+                        &obj.meta.building.as_ref().map(|s| s.as_str()),
+                        &obj.meta.entrance.as_ref().map(|s| s.as_str()),
+                        &obj.meta.footprint_area,
+                        &obj.meta.version,
+                        &obj.meta.timestamp,
+                        &obj.meta.certified,
+                        &obj.meta.origin.as_ref().map(|s| s.as_str()),
+                        &obj.meta.country.as_ref().map(|s| s.as_str()),
+                        &obj.meta.source_file.as_ref().map(|s| s.as_str()),
                     ]) {
                         Some((obj, e.to_string()))
                     } else {
@@ -347,20 +535,11 @@ impl DB {
         };
         if !errors.is_empty() {
             let mut stmt = tx
-                .prepare(
-                    "INSERT INTO addresses_errors(
-                    lat,
-                    lon,
-                    number,
-                    street,
-                    unit,
-                    city,
-                    district,
-                    region,
-                    postcode,
-                    kind
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-                )
+                .prepare(&format!(
+                    "INSERT INTO addresses_errors({}, kind) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, \
+                    ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
+                    ADDRESS_COLUMNS
+                ))
                 .expect("failed to prepare error statement");
 
             for (obj, err) in errors.drain(..) {
@@ -374,14 +553,134 @@ impl DB {
                     &obj.district.as_ref().map(|s| s.as_str()),
                     &obj.region.as_ref().map(|s| s.as_str()),
                     &obj.postcode.as_ref().map(|s| s.as_str()),
+                    &obj.source_id.as_ref().map(|s| s.as_str()),
+                    &obj.meta.name.as_ref().map(|s| s.as_str()),
+                    &obj.meta.object_kind.as_ref().map(|s| s.as_str()),
+                    &obj.meta.building.as_ref().map(|s| s.as_str()),
+                    &obj.meta.entrance.as_ref().map(|s| s.as_str()),
+                    &obj.meta.footprint_area,
+                    &obj.meta.version,
+                    &obj.meta.timestamp,
+                    &obj.meta.certified,
+                    &obj.meta.origin.as_ref().map(|s| s.as_str()),
+                    &obj.meta.country.as_ref().map(|s| s.as_str()),
+                    &obj.meta.source_file.as_ref().map(|s| s.as_str()),
                     &err,
                 ])
                 .expect("failed to insert into errors");
             }
         }
 
+        if !self.streets_buffer.is_empty() {
+            let mut stmt = tx
+                .prepare(
+                    "INSERT INTO streets(
+                    name,
+                    city,
+                    lat,
+                    lon,
+                    min_lat,
+                    min_lon,
+                    max_lat,
+                    max_lon,
+                    source_id
+                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                )
+                .expect("failed to prepare streets statement");
+
+            for street in self.streets_buffer.drain(..) {
+                stmt.execute([
+                    &street.name.as_str() as &dyn ToSql,
+                    &street.city.as_ref().map(|s| s.as_str()),
+                    &street.lat,
+                    &street.lon,
+                    &street.min_lat,
+                    &street.min_lon,
+                    &street.max_lat,
+                    &street.max_lon,
+                    &street.source_id.as_ref().map(|s| s.as_str()),
+                ])
+                .expect("failed to insert street");
+            }
+        }
+
         tx.commit().expect("commit failed");
     }
+
+    /// Counts the number of inserted streets.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use tools::{CompatibleDB, Street, DB};
+    ///
+    /// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+    /// db.insert_street(Street {
+    ///     name: "rue des champignons".into(),
+    ///     lat: 0.,
+    ///     lon: 0.,
+    ///     ..Street::default()
+    /// });
+    /// assert_eq!(db.get_nb_streets(), 1);
+    /// ```
+    pub fn get_nb_streets(&mut self) -> i64 {
+        self.flush();
+        self.conn
+            .query_row("SELECT COUNT(*) FROM streets", [], |row| row.get(0))
+            .expect("failed to count streets")
+    }
+
+    /// Removes the addresses read from the object `source_id` of their source and returns them.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use tools::DB;
+    ///
+    /// let mut db = DB::new("addresses.db", 10000, false).expect("failed to create DB");
+    /// let removed = db.remove_by_source_id("node/123");
+    /// ```
+    pub fn remove_by_source_id(&mut self, source_id: &str) -> Vec<Address> {
+        self.flush();
+        let removed = {
+            let mut stmt = self
+                .conn
+                .prepare(&format!(
+                    "SELECT {} FROM addresses WHERE source_id=?1",
+                    ADDRESS_COLUMNS
+                ))
+                .expect("failed to prepare statement");
+            stmt.query_map([source_id], |row| row.try_into())
+                .expect("failed to query addresses")
+                .map(|x| x.expect("failed parsing address"))
+                .collect()
+        };
+        self.conn
+            .execute("DELETE FROM addresses WHERE source_id=?1", [source_id])
+            .expect("failed to remove addresses");
+        removed
+    }
+
+    /// Replaces the street of the addresses read from the object `source_id` of their source.
+    /// Returns the number of updated addresses.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use tools::DB;
+    ///
+    /// let mut db = DB::new("addresses.db", 10000, false).expect("failed to create DB");
+    /// db.set_street_by_source_id("way/123", "rue des champignons");
+    /// ```
+    pub fn set_street_by_source_id(&mut self, source_id: &str, street: &str) -> usize {
+        self.flush();
+        self.conn
+            .execute(
+                "UPDATE OR IGNORE addresses SET street=?2 WHERE source_id=?1",
+                [source_id, street],
+            )
+            .expect("failed to update addresses")
+    }
 }
 
 /// A trait used by importers. If you want to use another type than `DB`, you'll have to implement
@@ -405,9 +704,28 @@ pub trait CompatibleDB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// ```
     fn insert(&mut self, addr: Address);
+    /// Inserts a new street. Streets are ignored by default, as most databases only store
+    /// addresses.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use tools::{CompatibleDB, Street, DB};
+    ///
+    /// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+    /// db.insert_street(Street {
+    ///     name: "rue des champignons".into(),
+    ///     lat: 0.,
+    ///     lon: 0.,
+    ///     ..Street::default()
+    /// });
+    /// ```
+    fn insert_street(&mut self, _street: Street) {}
     /// Counts the number of different inserted cities.
     ///
     /// Example:
@@ -427,6 +745,8 @@ pub trait CompatibleDB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// assert_eq!(db.get_nb_cities(), 1);
     /// ```
@@ -450,6 +770,8 @@ pub trait CompatibleDB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// assert_eq!(db.get_nb_addresses(), 1);
     /// ```
@@ -475,6 +797,8 @@ pub trait CompatibleDB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// assert_eq!(db.get_nb_errors(), 1);
     /// ```
@@ -499,6 +823,8 @@ pub trait CompatibleDB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// assert_eq!(db.get_nb_by_errors_kind(), vec![("Missing mandataory field".into(), 1)]);
     /// ```
@@ -522,6 +848,8 @@ pub trait CompatibleDB {
     ///     district: None,
     ///     region: None,
     ///     postcode: None,
+    ///     source_id: None,
+    ///     meta: Default::default(),
     /// });
     /// assert_eq!(db.get_address(12, "rue des champignons"),
     ///            vec![Address {
@@ -534,9 +862,50 @@ pub trait CompatibleDB {
     ///                 district: None,
     ///                 region: None,
     ///                 postcode: None,
+    ///                 source_id: None,
+    ///                 meta: Default::default(),
     ///             }]);
     /// ```
     fn get_address(&mut self, housenumber: i32, street: &str) -> Vec<Address>;
+    /// Returns the addresses located in the bounding box `[min_lon, min_lat, max_lon, max_lat]`.
+    /// Databases which can't be queried return no address, which is the default.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use tools::{Address, CompatibleDB, DB};
+    ///
+    /// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+    /// db.insert(Address {
+    ///     lat: 48.85,
+    ///     lon: 2.35,
+    ///     number: Some("12".into()),
+    ///     street: Some("rue des champignons".into()),
+    ///     ..Address::default()
+    /// });
+    /// assert_eq!(db.get_addresses_in_bbox([2.3, 48.8, 2.4, 48.9]).len(), 1);
+    /// ```
+    fn get_addresses_in_bbox(&mut self, _bbox: [f64; 4]) -> Vec<Address> {
+        Vec::new()
+    }
+    /// Removes an address equal to `addr` which was inserted before. Does nothing by default.
+    ///
+    /// Example:
+    ///
+    /// ```no_run
+    /// use tools::{Address, CompatibleDB, DB};
+    ///
+    /// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+    /// let address = Address {
+    ///     number: Some("12".into()),
+    ///     street: Some("rue des champignons".into()),
+    ///     ..Address::default()
+    /// };
+    /// db.insert(address.clone());
+    /// db.remove(&address);
+    /// assert_eq!(db.get_nb_addresses(), 0);
+    /// ```
+    fn remove(&mut self, _addr: &Address) {}
 }
 
 impl CompatibleDB for DB {
@@ -550,6 +919,13 @@ impl CompatibleDB for DB {
         }
     }
 
+    fn insert_street(&mut self, street: Street) {
+        self.streets_buffer.push(street);
+        if self.streets_buffer.len() >= self.db_buffer_size {
+            self.flush();
+        }
+    }
+
     fn get_nb_cities(&mut self) -> i64 {
         self.flush();
         let mut stmt = self
@@ -601,14 +977,64 @@ impl CompatibleDB for DB {
 
     fn get_address(&mut self, housenumber: i32, street: &str) -> Vec<Address> {
         self.flush();
-        let mut stmt = self.conn
-            .prepare("SELECT lat, lon, number, street, unit, city, district, region, postcode FROM addresses WHERE number=?1 AND street=?2")
+        let mut stmt = self
+            .conn
+            .prepare(&format!(
+                "SELECT {} FROM addresses WHERE number=?1 AND street=?2",
+                ADDRESS_COLUMNS
+            ))
             .expect("failed to prepare statement");
         stmt.query_map(&[&housenumber as &dyn ToSql, &street], |row| row.try_into())
             .expect("failed to insert into errors")
             .map(|x| x.expect("failed parsing address"))
             .collect()
     }
+
+    fn get_addresses_in_bbox(&mut self, bbox: [f64; 4]) -> Vec<Address> {
+        let [min_lon, min_lat, max_lon, max_lat] = bbox;
+        let in_bbox = |addr: &Address| {
+            (min_lat..=max_lat).contains(&addr.lat) && (min_lon..=max_lon).contains(&addr.lon)
+        };
+
+        // The buffer is searched as well instead of being flushed, as this may be called for each
+        // inserted address.
+        let mut addresses: Vec<_> = self.buffer.iter().filter(|a| in_bbox(a)).cloned().collect();
+        let mut stmt = self
+            .conn
+            .prepare_cached(&format!(
+                "SELECT {} FROM addresses WHERE lat BETWEEN ?1 AND ?2 AND lon BETWEEN ?3 AND ?4",
+                ADDRESS_COLUMNS
+            ))
+            .expect("failed to prepare statement");
+        addresses.extend(
+            stmt.query_map([min_lat, max_lat, min_lon, max_lon], |row| row.try_into())
+                .expect("failed to query addresses")
+                .map(|x| x.expect("failed parsing address")),
+        );
+        addresses
+    }
+
+    fn remove(&mut self, addr: &Address) {
+        if let Some(index) = self.buffer.iter().position(|a| a == addr) {
+            self.buffer.remove(index);
+            return;
+        }
+
+        self.conn
+            .prepare_cached(
+                "DELETE FROM addresses WHERE lat=?1 AND lon=?2 AND number=?3 AND street=?4 \
+                AND city IS ?5",
+            )
+            .expect("failed to prepare statement")
+            .execute([
+                &addr.lat as &dyn ToSql,
+                &addr.lon,
+                &addr.number.as_ref().map(|s| s.as_str()),
+                &addr.street.as_ref().map(|s| s.as_str()),
+                &addr.city.as_ref().map(|s| s.as_str()),
+            ])
+            .expect("failed to remove address");
+    }
 }
 
 impl Drop for DB {
@@ -616,3 +1042,41 @@ impl Drop for DB {
         self.flush();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_bbox_and_remove() {
+        let db_file = "check_bbox_and_remove.db";
+        let mut db = DB::new(db_file, 2, true).expect("Failed to initialize DB");
+
+        let address = |number: &str, lat, lon| Address {
+            lat,
+            lon,
+            number: Some(number.into()),
+            street: Some("rue des champignons".into()),
+            ..Address::default()
+        };
+
+        // The first two addresses are flushed, the last one stays in the buffer
+        db.insert(address("1", 48.85, 2.35));
+        db.insert(address("2", 48.86, 2.36));
+        db.insert(address("3", 48.85, 2.35));
+
+        let mut found = db.get_addresses_in_bbox([2.34, 48.84, 2.355, 48.855]);
+        found.sort_by(|a, b| a.number.cmp(&b.number));
+        assert_eq!(
+            found,
+            [address("1", 48.85, 2.35), address("3", 48.85, 2.35)]
+        );
+
+        db.remove(&address("1", 48.85, 2.35));
+        db.remove(&address("3", 48.85, 2.35));
+        assert_eq!(db.get_nb_addresses(), 1);
+        assert_eq!(db.get_addresses_in_bbox([2.34, 48.84, 2.355, 48.855]), []);
+
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+}
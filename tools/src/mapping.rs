@@ -0,0 +1,247 @@
+//! Mapping of the fields of records, such as the columns of a CSV file or the properties of a
+//! GeoJSON feature, to addresses. It is used by the importers which are configured by a file rather
+//! than written for a given source.
+
+use std::borrow::Cow;
+
+use serde::Deserialize;
+use smartstring::alias::String;
+
+use crate::{Address, AddressMeta};
+
+/// A column of a record: its name, or its index (starting at 0) for files without a header.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum Column {
+    Index(usize),
+    Name(String),
+}
+
+/// How the value of a field of an address is built from a record.
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum FieldMapping {
+    /// The value of a column.
+    Column(Column),
+    /// The values of several columns joined with spaces, such as a number and its suffix. Empty
+    /// values are skipped.
+    Columns(Vec<Column>),
+    /// The values of several columns joined with `separator`. Empty values are skipped.
+    Join {
+        columns: Vec<Column>,
+        separator: String,
+    },
+    /// A constant value, such as the country of a national dataset.
+    Constant { value: String },
+}
+
+/// A record from which an address is read.
+pub trait Record {
+    /// Returns the value of a column, if any.
+    fn get(&self, column: &Column) -> Option<Cow<'_, str>>;
+}
+
+impl FieldMapping {
+    /// Returns the value of the field for a record, trimmed, or `None` if it is empty.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use std::borrow::Cow;
+    /// use tools::{Column, FieldMapping, Record};
+    ///
+    /// struct Row(Vec<&'static str>);
+    ///
+    /// impl Record for Row {
+    ///     fn get(&self, column: &Column) -> Option<Cow<'_, str>> {
+    ///         match column {
+    ///             Column::Index(index) => self.0.get(*index).map(|x| Cow::Borrowed(*x)),
+    ///             Column::Name(_) => None,
+    ///         }
+    ///     }
+    /// }
+    ///
+    /// let row = Row(vec!["12", "bis", " rue Buffon "]);
+    /// let number = FieldMapping::Columns(vec![Column::Index(0), Column::Index(1)]);
+    /// assert_eq!(number.value(&row).as_deref(), Some("12 bis"));
+    ///
+    /// let street = FieldMapping::Column(Column::Index(2));
+    /// assert_eq!(street.value(&row).as_deref(), Some("rue Buffon"));
+    /// ```
+    pub fn value(&self, record: &impl Record) -> Option<String> {
+        let join = |columns: &[Column], separator: &str| {
+            let mut res = String::new();
+
+            for value in columns.iter().filter_map(|column| record.get(column)) {
+                let value = value.trim();
+
+                if !value.is_empty() {
+                    if !res.is_empty() {
+                        res.push_str(separator);
+                    }
+
+                    res.push_str(value);
+                }
+            }
+
+            res
+        };
+
+        let value = match self {
+            Self::Column(column) => record.get(column).map(|x| x.trim().into()),
+            Self::Columns(columns) => Some(join(columns, " ")),
+            Self::Join { columns, separator } => Some(join(columns, separator)),
+            Self::Constant { value } => Some(value.clone()),
+        };
+
+        value.filter(|value| !value.is_empty())
+    }
+
+    /// Returns the columns read by this field.
+    pub fn columns(&self) -> &[Column] {
+        match self {
+            Self::Column(column) => std::slice::from_ref(column),
+            Self::Columns(columns) | Self::Join { columns, .. } => columns,
+            Self::Constant { .. } => &[],
+        }
+    }
+}
+
+/// Mapping of the fields of an address. Coordinates may be left out for records which come with a
+/// geometry, such as GeoJSON features.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AddressMapping {
+    pub lat: Option<FieldMapping>,
+    pub lon: Option<FieldMapping>,
+    pub number: Option<FieldMapping>,
+    pub street: Option<FieldMapping>,
+    pub unit: Option<FieldMapping>,
+    pub city: Option<FieldMapping>,
+    pub district: Option<FieldMapping>,
+    pub region: Option<FieldMapping>,
+    pub postcode: Option<FieldMapping>,
+    pub source_id: Option<FieldMapping>,
+    /// See [`AddressMeta::country`].
+    pub country: Option<FieldMapping>,
+    /// See [`AddressMeta::origin`].
+    pub origin: Option<FieldMapping>,
+}
+
+impl AddressMapping {
+    /// Returns all the fields which are mapped.
+    pub fn fields(&self) -> impl Iterator<Item = &FieldMapping> {
+        [
+            &self.lat,
+            &self.lon,
+            &self.number,
+            &self.street,
+            &self.unit,
+            &self.city,
+            &self.district,
+            &self.region,
+            &self.postcode,
+            &self.source_id,
+            &self.country,
+            &self.origin,
+        ]
+        .into_iter()
+        .flatten()
+    }
+
+    /// Build an address from a record. Its coordinates are read from the `lat` and `lon` fields
+    /// when they are mapped, and are `position` (as `[lon, lat]`) otherwise. Returns `None` if
+    /// there are no coordinates, or if they can't be parsed. A comma is accepted as a decimal
+    /// separator.
+    pub fn address(&self, record: &impl Record, position: Option<[f64; 2]>) -> Option<Address> {
+        let get = |field: &Option<FieldMapping>| field.as_ref()?.value(record);
+
+        let coordinate = |field: &Option<FieldMapping>| -> Option<f64> {
+            get(field)?.replace(',', ".").parse().ok()
+        };
+
+        let [lon, lat] = match (&self.lon, &self.lat) {
+            (Some(_), Some(_)) => [coordinate(&self.lon)?, coordinate(&self.lat)?],
+            _ => position?,
+        };
+
+        Some(Address {
+            lat,
+            lon,
+            number: get(&self.number),
+            street: get(&self.street),
+            unit: get(&self.unit),
+            city: get(&self.city),
+            district: get(&self.district),
+            region: get(&self.region),
+            postcode: get(&self.postcode),
+            source_id: get(&self.source_id),
+            meta: AddressMeta {
+                country: get(&self.country),
+                origin: get(&self.origin),
+                ..AddressMeta::default()
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    impl Record for HashMap<&str, &str> {
+        fn get(&self, column: &Column) -> Option<Cow<'_, str>> {
+            match column {
+                Column::Name(name) => self.get(name.as_str()).map(|x| Cow::Borrowed(*x)),
+                Column::Index(_) => None,
+            }
+        }
+    }
+
+    #[test]
+    fn check_address() {
+        let name = |name: &str| Column::Name(name.into());
+
+        let mapping = AddressMapping {
+            lat: Some(FieldMapping::Column(name("y"))),
+            lon: Some(FieldMapping::Column(name("x"))),
+            number: Some(FieldMapping::Join {
+                columns: vec![name("num"), name("suffix")],
+                separator: "".into(),
+            }),
+            street: Some(FieldMapping::Column(name("street"))),
+            unit: Some(FieldMapping::Column(name("unit"))),
+            country: Some(FieldMapping::Constant { value: "CH".into() }),
+            ..AddressMapping::default()
+        };
+
+        let record = HashMap::from([
+            ("x", "7,4474"),
+            ("y", "46.9480"),
+            ("num", "12"),
+            ("suffix", "a"),
+            ("street", "Bundesplatz"),
+            ("unit", " "),
+        ]);
+
+        let address = mapping.address(&record, None).unwrap();
+        assert_eq!((address.lon, address.lat), (7.4474, 46.948));
+        assert_eq!(address.number.as_deref(), Some("12a"));
+        assert_eq!(address.street.as_deref(), Some("Bundesplatz"));
+        assert_eq!(address.unit, None);
+        assert_eq!(address.meta.country.as_deref(), Some("CH"));
+        assert_eq!(mapping.fields().count(), 6);
+
+        let record = HashMap::from([("x", "7.4474"), ("y", "")]);
+        assert_eq!(mapping.address(&record, None), None);
+
+        let mapping = AddressMapping {
+            lat: None,
+            lon: None,
+            ..mapping
+        };
+        let address = mapping.address(&record, Some([7.5, 47.])).unwrap();
+        assert_eq!((address.lon, address.lat), (7.5, 47.));
+    }
+}
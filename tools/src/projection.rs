@@ -0,0 +1,415 @@
+//! Reprojection of coordinates from the projected coordinate reference systems commonly used by
+//! national datasets to WGS84 longitudes and latitudes.
+
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_4};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::Deserialize;
+
+use crate::valid_coordinates;
+
+/// Semi-major axis of the GRS80 and WGS84 ellipsoids, in meters.
+const GRS80_A: f64 = 6_378_137.;
+/// First eccentricity of the GRS80 ellipsoid, which is the same as WGS84's up to the millimeter.
+const GRS80_E: f64 = 0.081_819_191_042_815_79;
+
+/// A coordinate reference system which coordinates can be reprojected from. Datums based on GRS80,
+/// such as RGF93 and ETRS89, are treated as WGS84, which they match up to the meter.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(try_from = "String")]
+pub enum Crs {
+    /// Longitudes and latitudes (`EPSG:4326`).
+    #[default]
+    Wgs84,
+    /// Lambert-93, used in metropolitan France (`EPSG:2154`).
+    Lambert93,
+    /// RD New, used in the Netherlands (`EPSG:28992`).
+    RdNew,
+    /// LV95, used in Switzerland and Liechtenstein (`EPSG:2056`).
+    Lv95,
+    /// LV03, the former Swiss system (`EPSG:21781`).
+    Lv03,
+    /// A zone of the Universal Transverse Mercator projection, on WGS84 (`EPSG:326xx` and
+    /// `EPSG:327xx`) or ETRS89 (`EPSG:258xx`).
+    Utm { zone: u8, north: bool },
+}
+
+/// Why coordinates can't be reprojected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectionError {
+    /// The coordinates aren't finite, or don't give a valid longitude and latitude.
+    InvalidCoordinates,
+    /// The coordinates are outside of the area where the coordinate reference system is used,
+    /// which is its country for national systems.
+    OutOfArea,
+}
+
+impl fmt::Display for ProjectionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidCoordinates => "invalid coordinates",
+            Self::OutOfArea => "coordinates out of the area of the coordinate reference system",
+        })
+    }
+}
+
+impl Crs {
+    /// Returns the coordinate reference system with the given EPSG code, if it is supported.
+    pub fn from_epsg(code: u32) -> Option<Self> {
+        Some(match code {
+            4326 => Self::Wgs84,
+            2154 => Self::Lambert93,
+            28992 => Self::RdNew,
+            2056 => Self::Lv95,
+            21781 => Self::Lv03,
+            32601..=32660 => Self::Utm {
+                zone: (code - 32600) as u8,
+                north: true,
+            },
+            32701..=32760 => Self::Utm {
+                zone: (code - 32700) as u8,
+                north: false,
+            },
+            25828..=25838 => Self::Utm {
+                zone: (code - 25800) as u8,
+                north: true,
+            },
+            _ => return None,
+        })
+    }
+
+    /// Returns the EPSG code of the coordinate reference system. ETRS89 UTM zones are given as
+    /// their WGS84 counterpart.
+    pub fn epsg(&self) -> u32 {
+        match *self {
+            Self::Wgs84 => 4326,
+            Self::Lambert93 => 2154,
+            Self::RdNew => 28992,
+            Self::Lv95 => 2056,
+            Self::Lv03 => 21781,
+            Self::Utm { zone, north: true } => 32600 + u32::from(zone),
+            Self::Utm { zone, north: false } => 32700 + u32::from(zone),
+        }
+    }
+
+    /// Returns the area where the coordinate reference system is used, as
+    /// `[min_lon, min_lat, max_lon, max_lat]`. Coordinates of UTM zones are commonly used for a
+    /// whole country even if it spans over the next zones, so the area of a zone is widened by a
+    /// zone on each side.
+    pub fn area(&self) -> [f64; 4] {
+        match *self {
+            Self::Wgs84 => [-180., -90., 180., 90.],
+            Self::Lambert93 => [-9.86, 41.15, 10.38, 51.56],
+            Self::RdNew => [3.2, 50.75, 7.22, 53.7],
+            Self::Lv95 | Self::Lv03 => [5.96, 45.82, 10.49, 47.81],
+            Self::Utm { zone, north } => {
+                let central = central_meridian(zone);
+                let (min_lat, max_lat) = if north { (0., 84.) } else { (-80., 0.) };
+                [central - 9., min_lat, central + 9., max_lat]
+            }
+        }
+    }
+
+    /// Reprojects coordinates to a WGS84 longitude and latitude, returned as `[lon, lat]`. The
+    /// result must be within the [area](Crs::area) of the coordinate reference system.
+    ///
+    /// Example:
+    ///
+    /// ```
+    /// use tools::{Crs, ProjectionError};
+    ///
+    /// let crs: Crs = "EPSG:2154".parse().unwrap();
+    /// let [lon, lat] = crs.to_wgs84(700_000., 6_600_000.).unwrap();
+    /// assert!((lon - 3.).abs() < 1e-9 && (lat - 46.5).abs() < 1e-9);
+    ///
+    /// // Amsterdam in Lambert-93
+    /// assert_eq!(crs.to_wgs84(1_000_000., 7_300_000.), Err(ProjectionError::OutOfArea));
+    /// ```
+    pub fn to_wgs84(&self, x: f64, y: f64) -> Result<[f64; 2], ProjectionError> {
+        if !x.is_finite() || !y.is_finite() {
+            return Err(ProjectionError::InvalidCoordinates);
+        }
+
+        let [lon, lat] = match *self {
+            Self::Wgs84 => [x, y],
+            Self::Lambert93 => lambert93_to_wgs84(x, y),
+            Self::RdNew => rd_new_to_wgs84(x, y),
+            Self::Lv95 => swiss_to_wgs84(x - 2_600_000., y - 1_200_000.),
+            Self::Lv03 => swiss_to_wgs84(x - 600_000., y - 200_000.),
+            Self::Utm { zone, north } => utm_to_wgs84(zone, north, x, y),
+        };
+
+        if !valid_coordinates(lon, lat) {
+            return Err(ProjectionError::InvalidCoordinates);
+        }
+
+        let [min_lon, min_lat, max_lon, max_lat] = self.area();
+
+        if (min_lon..=max_lon).contains(&lon) && (min_lat..=max_lat).contains(&lat) {
+            Ok([lon, lat])
+        } else {
+            Err(ProjectionError::OutOfArea)
+        }
+    }
+}
+
+impl fmt::Display for Crs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "EPSG:{}", self.epsg())
+    }
+}
+
+impl FromStr for Crs {
+    type Err = String;
+
+    /// Reads a coordinate reference system such as `EPSG:2154`, its URN
+    /// (`urn:ogc:def:crs:EPSG::2154`) or `WGS84`.
+    fn from_str(s: &str) -> Result<Self, String> {
+        let s = s.trim();
+
+        if s.eq_ignore_ascii_case("WGS84")
+            || s.eq_ignore_ascii_case("urn:ogc:def:crs:OGC:1.3:CRS84")
+        {
+            return Ok(Self::Wgs84);
+        }
+
+        let upper = s.to_ascii_uppercase();
+        let code = upper
+            .strip_prefix("EPSG:")
+            .or_else(|| upper.strip_prefix("URN:OGC:DEF:CRS:EPSG::"))
+            .and_then(|code| code.parse().ok());
+
+        match code {
+            Some(code) => Self::from_epsg(code)
+                .ok_or_else(|| format!("unsupported coordinate reference system `{}`", s)),
+            None => Err(format!("unknown coordinate reference system `{}`", s)),
+        }
+    }
+}
+
+impl TryFrom<String> for Crs {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, String> {
+        s.parse()
+    }
+}
+
+/// Longitude of the central meridian of a UTM zone, in degrees.
+fn central_meridian(zone: u8) -> f64 {
+    f64::from(zone) * 6. - 183.
+}
+
+/// Inverse of the Lambert Conformal Conic projection with two standard parallels, on GRS80, with
+/// the parameters of Lambert-93.
+fn lambert93_to_wgs84(x: f64, y: f64) -> [f64; 2] {
+    let (lat0, lon0) = (46.5f64.to_radians(), 3f64.to_radians());
+    let (lat1, lat2) = (49f64.to_radians(), 44f64.to_radians());
+    let (x0, y0) = (700_000., 6_600_000.);
+
+    let e = GRS80_E;
+    let m = |lat: f64| lat.cos() / (1. - (e * lat.sin()).powi(2)).sqrt();
+    let t = |lat: f64| {
+        let e_sin = e * lat.sin();
+        (FRAC_PI_4 - lat / 2.).tan() / ((1. - e_sin) / (1. + e_sin)).powf(e / 2.)
+    };
+
+    let n = (m(lat1).ln() - m(lat2).ln()) / (t(lat1).ln() - t(lat2).ln());
+    let f = m(lat1) / (n * t(lat1).powf(n));
+    let rho0 = GRS80_A * f * t(lat0).powf(n);
+
+    let (dx, dy) = (x - x0, rho0 - (y - y0));
+    let rho = (dx * dx + dy * dy).sqrt().copysign(n);
+    let t = (rho / (GRS80_A * f)).powf(1. / n);
+    let lon = dx.atan2(dy) / n + lon0;
+
+    // The latitude is found iteratively, which converges in a few steps.
+    let mut lat = FRAC_PI_2 - 2. * t.atan();
+
+    for _ in 0..10 {
+        let e_sin = e * lat.sin();
+        let next = FRAC_PI_2 - 2. * (t * ((1. - e_sin) / (1. + e_sin)).powf(e / 2.)).atan();
+
+        if (next - lat).abs() < 1e-12 {
+            lat = next;
+            break;
+        }
+
+        lat = next;
+    }
+
+    [lon.to_degrees(), lat.to_degrees()]
+}
+
+/// Approximation of RD New coordinates to WGS84, accurate to about a meter, from the polynomials
+/// published by the Dutch Kadaster.
+fn rd_new_to_wgs84(x: f64, y: f64) -> [f64; 2] {
+    let dx = (x - 155_000.) * 1e-5;
+    let dy = (y - 463_000.) * 1e-5;
+
+    let lat = 3235.65389 * dy
+        - 32.58297 * dx.powi(2)
+        - 0.2475 * dy.powi(2)
+        - 0.84978 * dx.powi(2) * dy
+        - 0.0655 * dy.powi(3)
+        - 0.01709 * dx.powi(2) * dy.powi(2)
+        - 0.00738 * dx
+        + 0.0053 * dx.powi(4)
+        - 0.00039 * dx.powi(2) * dy.powi(3)
+        + 0.00033 * dx.powi(4) * dy
+        - 0.00012 * dx * dy;
+
+    let lon = 5260.52916 * dx + 105.94684 * dx * dy + 2.45656 * dx * dy.powi(2)
+        - 0.81885 * dx.powi(3)
+        + 0.05594 * dx * dy.powi(3)
+        - 0.05607 * dx.powi(3) * dy
+        + 0.01199 * dy
+        - 0.00256 * dx.powi(3) * dy.powi(2)
+        + 0.00128 * dx * dy.powi(4)
+        + 0.00022 * dy.powi(2)
+        - 0.00022 * dx.powi(2)
+        + 0.00026 * dx.powi(5);
+
+    [5.38720621 + lon / 3600., 52.15517440 + lat / 3600.]
+}
+
+/// Approximation of Swiss coordinates to WGS84, accurate to about a meter, from the formulas
+/// published by swisstopo. The coordinates are relative to the origin of the projection, in Bern.
+fn swiss_to_wgs84(east: f64, north: f64) -> [f64; 2] {
+    let y = east / 1e6;
+    let x = north / 1e6;
+
+    let lon =
+        2.6779094 + 4.728982 * y + 0.791484 * y * x + 0.1306 * y * x.powi(2) - 0.0436 * y.powi(3);
+    let lat = 16.9023892 + 3.238272 * x
+        - 0.270978 * y.powi(2)
+        - 0.002528 * x.powi(2)
+        - 0.0447 * y.powi(2) * x
+        - 0.0140 * x.powi(3);
+
+    // Results are in units of 10000", converted to degrees
+    [lon * 100. / 36., lat * 100. / 36.]
+}
+
+/// Inverse of the Transverse Mercator projection on WGS84 with the parameters of UTM zones, from
+/// the series given by Snyder in "Map Projections: A Working Manual".
+fn utm_to_wgs84(zone: u8, north: bool, x: f64, y: f64) -> [f64; 2] {
+    let k0 = 0.9996;
+    let e2 = GRS80_E * GRS80_E;
+    let ep2 = e2 / (1. - e2);
+
+    let x = x - 500_000.;
+    let y = if north { y } else { y - 10_000_000. };
+
+    let mu = y / k0 / (GRS80_A * (1. - e2 / 4. - 3. * e2.powi(2) / 64. - 5. * e2.powi(3) / 256.));
+    let e1 = (1. - (1. - e2).sqrt()) / (1. + (1. - e2).sqrt());
+    let lat1 = mu
+        + (3. * e1 / 2. - 27. * e1.powi(3) / 32.) * (2. * mu).sin()
+        + (21. * e1.powi(2) / 16. - 55. * e1.powi(4) / 32.) * (4. * mu).sin()
+        + (151. * e1.powi(3) / 96.) * (6. * mu).sin()
+        + (1097. * e1.powi(4) / 512.) * (8. * mu).sin();
+
+    let (sin1, cos1, tan1) = (lat1.sin(), lat1.cos(), lat1.tan());
+    let c1 = ep2 * cos1 * cos1;
+    let t1 = tan1 * tan1;
+    let n1 = GRS80_A / (1. - e2 * sin1 * sin1).sqrt();
+    let r1 = GRS80_A * (1. - e2) / (1. - e2 * sin1 * sin1).powf(1.5);
+    let d = x / (n1 * k0);
+
+    let lat = lat1
+        - (n1 * tan1 / r1)
+            * (d.powi(2) / 2.
+                - (5. + 3. * t1 + 10. * c1 - 4. * c1 * c1 - 9. * ep2) * d.powi(4) / 24.
+                + (61. + 90. * t1 + 298. * c1 + 45. * t1 * t1 - 252. * ep2 - 3. * c1 * c1)
+                    * d.powi(6)
+                    / 720.);
+    let lon = (d - (1. + 2. * t1 + c1) * d.powi(3) / 6.
+        + (5. - 2. * c1 + 28. * t1 - 3. * c1 * c1 + 8. * ep2 + 24. * t1 * t1) * d.powi(5) / 120.)
+        / cos1;
+
+    [central_meridian(zone) + lon.to_degrees(), lat.to_degrees()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Check that a reprojected position is within about 10 meters of the expected one.
+    fn assert_close(crs: Crs, x: f64, y: f64, expected: [f64; 2]) {
+        let [lon, lat] = crs.to_wgs84(x, y).unwrap();
+        assert!(
+            (lon - expected[0]).abs() < 1e-4 && (lat - expected[1]).abs() < 1e-4,
+            "{}: got {:?}, expected {:?}",
+            crs,
+            [lon, lat],
+            expected
+        );
+    }
+
+    #[test]
+    fn check_parse() {
+        assert_eq!("EPSG:4326".parse(), Ok(Crs::Wgs84));
+        assert_eq!("wgs84".parse(), Ok(Crs::Wgs84));
+        assert_eq!("epsg:2154".parse(), Ok(Crs::Lambert93));
+        assert_eq!("urn:ogc:def:crs:EPSG::28992".parse(), Ok(Crs::RdNew));
+        assert_eq!(
+            "EPSG:25832".parse(),
+            Ok(Crs::Utm {
+                zone: 32,
+                north: true
+            })
+        );
+        assert_eq!(Crs::from_epsg(32718).unwrap().to_string(), "EPSG:32718");
+        assert!("EPSG:3857".parse::<Crs>().is_err());
+        assert!("Lambert".parse::<Crs>().is_err());
+    }
+
+    #[test]
+    fn check_to_wgs84() {
+        // Eiffel Tower
+        assert_close(
+            Crs::Lambert93,
+            648_237.16,
+            6_862_256.11,
+            [2.29450, 48.85826],
+        );
+        assert_close(
+            Crs::Utm {
+                zone: 31,
+                north: true,
+            },
+            448_251.86,
+            5_411_939.35,
+            [2.29450, 48.85826],
+        );
+        // Onze Lieve Vrouwetoren in Amersfoort, origin of RD
+        assert_close(Crs::RdNew, 155_000., 463_000., [5.38721, 52.15517]);
+        // Former observatory of Bern, origin of LV95 and LV03
+        assert_close(Crs::Lv95, 2_600_000., 1_200_000., [7.43864, 46.95108]);
+        assert_close(Crs::Lv03, 700_000., 100_000., [8.73049, 46.04412]);
+        // Cape Town
+        assert_close(
+            Crs::Utm {
+                zone: 34,
+                north: false,
+            },
+            261_881.6,
+            6_243_182.35,
+            [18.4241, -33.9249],
+        );
+
+        assert_eq!(
+            Crs::RdNew.to_wgs84(155_000., 1_463_000.),
+            Err(ProjectionError::OutOfArea)
+        );
+        assert_eq!(
+            Crs::Wgs84.to_wgs84(f64::NAN, 0.),
+            Err(ProjectionError::InvalidCoordinates)
+        );
+        assert_eq!(
+            Crs::Wgs84.to_wgs84(200., 0.),
+            Err(ProjectionError::InvalidCoordinates)
+        );
+    }
+}
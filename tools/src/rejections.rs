@@ -0,0 +1,99 @@
+//! Counters of the records which were read but not imported, such as features without a geometry.
+//! They are shared by the importers, so that the records they skip are reported the same way.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::GeometryError;
+
+/// Reason why a record is not imported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Rejection {
+    /// The record couldn't be parsed.
+    InvalidRecord,
+    /// The record has no geometry.
+    MissingGeometry,
+    /// The geometry couldn't be decoded.
+    InvalidGeometry,
+    /// The kind of geometry isn't supported by the importer, such as a line.
+    UnsupportedGeometry,
+    /// The coordinates of the geometry are missing, malformed or out of bounds.
+    InvalidCoordinates,
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Self::InvalidRecord => "invalid record",
+            Self::MissingGeometry => "missing geometry",
+            Self::InvalidGeometry => "invalid geometry",
+            Self::UnsupportedGeometry => "unsupported geometry",
+            Self::InvalidCoordinates => "invalid coordinates",
+        })
+    }
+}
+
+impl From<GeometryError> for Rejection {
+    fn from(err: GeometryError) -> Self {
+        match err {
+            GeometryError::Unsupported => Self::UnsupportedGeometry,
+            GeometryError::InvalidCoordinates => Self::InvalidCoordinates,
+        }
+    }
+}
+
+/// Number of records rejected while reading a file or a table, by reason.
+#[derive(Debug, Default)]
+pub struct Rejections(BTreeMap<Rejection, u64>);
+
+impl Rejections {
+    pub fn add(&mut self, reason: Rejection) {
+        *self.0.entry(reason).or_default() += 1;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Log the rejected records of `source` (such as a file or a table), if any. `importer` is the
+    /// name used as prefix of the messages of the importer, such as `GeoJSON`.
+    pub fn log_skipped(&self, importer: &str, source: impl fmt::Display) {
+        if !self.is_empty() {
+            crate::teprintln!("[{}] Skipped features in `{}`: {}", importer, source, self);
+        }
+    }
+}
+
+impl fmt::Display for Rejections {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (reason, count)) in self.0.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+
+            write!(f, "{} {}", count, reason)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_rejections() {
+        let mut rejections = Rejections::default();
+        assert!(rejections.is_empty());
+        assert_eq!(rejections.to_string(), "");
+
+        rejections.add(GeometryError::InvalidCoordinates.into());
+        rejections.add(Rejection::MissingGeometry);
+        rejections.add(Rejection::InvalidCoordinates);
+        assert_eq!(
+            rejections.to_string(),
+            "1 missing geometry, 2 invalid coordinates"
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Destinations of addresses which can be fed by several threads at once, such as importers
+//! reading several files in parallel.
+
+use std::sync::Mutex;
+
+use crate::{Address, CompatibleDB};
+
+/// A destination of addresses which can be shared by several threads. Unlike
+/// [`CompatibleDB::insert`], addresses are inserted through a shared reference.
+pub trait AddressSink: Sync {
+    /// Inserts a new address.
+    fn insert(&self, addr: Address);
+    /// Inserts several addresses, which is cheaper than inserting them one by one for sinks that
+    /// need to be locked.
+    fn insert_batch(&self, addrs: Vec<Address>) {
+        for addr in addrs {
+            self.insert(addr);
+        }
+    }
+}
+
+/// Wraps a [`CompatibleDB`] behind a lock so that it can be used as an [`AddressSink`].
+///
+/// Example:
+///
+/// ```no_run
+/// use tools::{Address, AddressSink, CompatibleDB, LockedDB, DB};
+///
+/// let mut db = DB::new("addresses.db", 10000, true).expect("failed to create DB");
+///
+/// let sink = LockedDB::new(&mut db);
+///
+/// std::thread::scope(|scope| {
+///     let sink = &sink;
+///
+///     for number in 1..=4 {
+///         scope.spawn(move || {
+///             sink.insert(Address {
+///                 number: Some(number.to_string().into()),
+///                 street: Some("rue des champignons".into()),
+///                 ..Address::default()
+///             })
+///         });
+///     }
+/// });
+///
+/// assert_eq!(db.get_nb_addresses(), 4);
+/// ```
+pub struct LockedDB<'a, T: CompatibleDB + Send>(Mutex<&'a mut T>);
+
+impl<'a, T: CompatibleDB + Send> LockedDB<'a, T> {
+    pub fn new(db: &'a mut T) -> Self {
+        Self(Mutex::new(db))
+    }
+}
+
+impl<'a, T: CompatibleDB + Send> AddressSink for LockedDB<'a, T> {
+    fn insert(&self, addr: Address) {
+        self.0.lock().expect("poisoned database lock").insert(addr);
+    }
+
+    fn insert_batch(&self, addrs: Vec<Address>) {
+        let mut db = self.0.lock().expect("poisoned database lock");
+
+        for addr in addrs {
+            db.insert(addr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::DB;
+
+    #[test]
+    fn check_locked_db() {
+        let db_file = "check_locked_db.db";
+        let mut db = DB::new(db_file, 0, true).expect("Failed to initialize DB");
+
+        let sink = LockedDB::new(&mut db);
+
+        std::thread::scope(|scope| {
+            let sink = &sink;
+
+            for thread in 0..4 {
+                scope.spawn(move || {
+                    let addrs = (0..100)
+                        .map(|number| Address {
+                            number: Some((thread * 100 + number).to_string().into()),
+                            street: Some("rue des champignons".into()),
+                            ..Address::default()
+                        })
+                        .collect();
+
+                    sink.insert_batch(addrs);
+                });
+            }
+        });
+
+        assert_eq!(db.get_nb_addresses(), 400);
+        let _ = std::fs::remove_file(db_file); // we ignore any potential error
+    }
+}